@@ -1,5 +1,15 @@
-use bench_align_offset::ALIGN_OFFSET_FNS;
-use criterion::{black_box as bb, criterion_group, criterion_main, BenchmarkId, Criterion};
+use bench_align_offset::ptr::AlignOffsetVariant;
+use bench_align_offset::simd::align_offsets_isa;
+use bench_align_offset::slice::align_to;
+use bench_align_offset::{
+    align_offset_euclid, align_offset_for, align_offset_v4, align_offsets, for_each_variant_marker,
+    Isa, VARIANTS,
+};
+use criterion::measurement::WallTime;
+use criterion::{
+    black_box as bb, criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion,
+    Throughput,
+};
 
 fn bench_align_offset(c: &mut Criterion) {
     let mut group = c.benchmark_group("args");
@@ -8,52 +18,208 @@ fn bench_align_offset(c: &mut Criterion) {
             .iter()
             .copied()
         {
-            for i in 0..ALIGN_OFFSET_FNS.len() {
+            for variant in VARIANTS {
                 group.bench_function(
-                    BenchmarkId::new(
-                        format!("align_offset_v{}", i),
-                        format!("({}, {}, {})", p, stride, align),
-                    ),
-                    |b| b.iter(|| unsafe { ALIGN_OFFSET_FNS[i](bb(p), bb(stride), bb(align)) }),
+                    BenchmarkId::new(variant.name, format!("({}, {}, {})", p, stride, align)),
+                    |b| b.iter(|| unsafe { (variant.func)(bb(p), bb(stride), bb(align)) }),
                 );
             }
-            for i in 0..ALIGN_OFFSET_FNS.len() {
+            for variant in VARIANTS {
                 group.bench_function(
-                    BenchmarkId::new(
-                        format!("align_offset_v{}", i),
-                        format!("({}, {}, {}*)", p, stride, align),
-                    ),
-                    |b| b.iter(|| unsafe { ALIGN_OFFSET_FNS[i](bb(p), bb(stride), align) }),
+                    BenchmarkId::new(variant.name, format!("({}, {}, {}*)", p, stride, align)),
+                    |b| b.iter(|| unsafe { (variant.func)(bb(p), bb(stride), align) }),
                 );
             }
-            for i in 0..ALIGN_OFFSET_FNS.len() {
+            for variant in VARIANTS {
                 group.bench_function(
-                    BenchmarkId::new(
-                        format!("align_offset_v{}", i),
-                        format!("({}, {}*, {})", p, stride, align),
-                    ),
-                    |b| b.iter(|| unsafe { ALIGN_OFFSET_FNS[i](bb(p), stride, bb(align)) }),
+                    BenchmarkId::new(variant.name, format!("({}, {}*, {})", p, stride, align)),
+                    |b| b.iter(|| unsafe { (variant.func)(bb(p), stride, bb(align)) }),
                 );
             }
-            for i in 0..ALIGN_OFFSET_FNS.len() {
+            for variant in VARIANTS {
                 group.bench_function(
-                    BenchmarkId::new(
-                        format!("align_offset_v{}", i),
-                        format!("({}, {}*, {}*)", p, stride, align),
-                    ),
-                    |b| b.iter(|| unsafe { ALIGN_OFFSET_FNS[i](bb(p), stride, align) }),
+                    BenchmarkId::new(variant.name, format!("({}, {}*, {}*)", p, stride, align)),
+                    |b| b.iter(|| unsafe { (variant.func)(bb(p), stride, align) }),
                 );
             }
+            // `align_offset_for` takes the stride as a type, so it is always constant.
+            assert_eq!(stride, core::mem::size_of::<[u8; 24]>());
+            group.bench_function(
+                BenchmarkId::new(
+                    "align_offset_for",
+                    format!("({}, {}*, {})", p, stride, align),
+                ),
+                |b| b.iter(|| unsafe { align_offset_for::<[u8; 24]>(bb(p), bb(align)) }),
+            );
+            group.bench_function(
+                BenchmarkId::new(
+                    "align_offset_for",
+                    format!("({}, {}*, {}*)", p, stride, align),
+                ),
+                |b| b.iter(|| unsafe { align_offset_for::<[u8; 24]>(bb(p), align) }),
+            );
         }
     }
     group.finish();
 }
 
+/// Number of pointers in the batch benchmarks.
+const N: usize = 1024;
+
+/// `N` pseudo-random, 8-byte aligned addresses, as returned by an allocator.
+fn allocator_like_ptrs() -> Vec<usize> {
+    (0..N as u64)
+        .map(|i| (i.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 20) as usize & !7)
+        .collect()
+}
+
+fn bench_latency_throughput(c: &mut Criterion) {
+    let ptrs = allocator_like_ptrs();
+    let mut out = vec![0usize; N];
+
+    // Each call's pointer depends on the previous call's result, so that the calls cannot
+    // overlap, and the time per element is the latency of a call.
+    let mut group = c.benchmark_group("latency");
+    group.throughput(Throughput::Elements(N as u64));
+    for stride in [24usize].iter().copied() {
+        for align in [16usize, 4096, 1 << 20].iter().copied() {
+            for variant in VARIANTS {
+                group.bench_function(
+                    BenchmarkId::new(variant.name, format!("({}, {})", stride, align)),
+                    |b| {
+                        b.iter(|| {
+                            let (stride, align) = (bb(stride), bb(align));
+                            let mut p = bb(ptrs[0]);
+                            for _ in 0..N {
+                                let o = unsafe { (variant.func)(p, stride, align) };
+                                // Stay 8-byte aligned, so that `p` can always be aligned.
+                                p = p.wrapping_add(8).wrapping_add(o << 3);
+                            }
+                            p
+                        })
+                    },
+                );
+            }
+        }
+    }
+    group.finish();
+
+    // The calls are independent, so that they can overlap, and the time per element is the
+    // reciprocal throughput of a call.
+    let mut group = c.benchmark_group("throughput");
+    group.throughput(Throughput::Elements(N as u64));
+    for stride in [24usize].iter().copied() {
+        for align in [16usize, 4096, 1 << 20].iter().copied() {
+            for variant in VARIANTS {
+                group.bench_function(
+                    BenchmarkId::new(variant.name, format!("({}, {})", stride, align)),
+                    |b| {
+                        b.iter(|| {
+                            let (stride, align) = (bb(stride), bb(align));
+                            for (&p, o) in bb(&ptrs).iter().zip(out.iter_mut()) {
+                                *o = unsafe { (variant.func)(p, stride, align) };
+                            }
+                        })
+                    },
+                );
+            }
+            // The batch API, against the variants called once per pointer.
+            group.bench_function(
+                BenchmarkId::new("align_offsets", format!("({}, {})", stride, align)),
+                |b| b.iter(|| unsafe { align_offsets(bb(&ptrs), bb(stride), bb(align), &mut out) }),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_align_offsets_simd(c: &mut Criterion) {
+    let ptrs = allocator_like_ptrs();
+    let mut out = vec![0usize; N];
+
+    let mut group = c.benchmark_group("simd");
+    group.throughput(Throughput::Elements(N as u64));
+    for stride in [24usize].iter().copied() {
+        for align in [16usize, 4096, 1 << 20].iter().copied() {
+            for isa in Isa::ALL.iter().copied().filter(|isa| isa.is_available()) {
+                group.bench_function(
+                    BenchmarkId::new(isa.name(), format!("({}, {})", stride, align)),
+                    |b| {
+                        b.iter(|| unsafe {
+                            align_offsets_isa(isa, bb(&ptrs), bb(stride), bb(align), &mut out)
+                        })
+                    },
+                );
+            }
+        }
+    }
+    group.finish();
+}
+
+fn bench_align_offset_euclid(c: &mut Criterion) {
+    let mut group = c.benchmark_group("euclid");
+    for (p, stride) in [(8usize, 24usize)].iter().copied() {
+        for align in [16usize, 4096, 1 << 20].iter().copied() {
+            group.bench_function(
+                BenchmarkId::new("align_offset_v4", format!("({}, {}, {})", p, stride, align)),
+                |b| b.iter(|| unsafe { align_offset_v4(bb(p), bb(stride), bb(align)) }),
+            );
+            group.bench_function(
+                BenchmarkId::new(
+                    "align_offset_euclid",
+                    format!("({}, {}, {})", p, stride, align),
+                ),
+                |b| b.iter(|| align_offset_euclid(bb(p), bb(stride), bb(align))),
+            );
+        }
+        // Moduli which are not powers of two, such as cache-set or page-colouring strides.
+        for m in [12usize, 4095, 1_000_003].iter().copied() {
+            group.bench_function(
+                BenchmarkId::new("align_offset_euclid", format!("({}, {}, {})", p, stride, m)),
+                |b| b.iter(|| align_offset_euclid(bb(p), bb(stride), bb(m))),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_align_to(c: &mut Criterion) {
+    fn bench_variant<V: AlignOffsetVariant>(
+        group: &mut BenchmarkGroup<'_, WallTime>,
+        slice: &[u8],
+        start: usize,
+    ) {
+        group.bench_function(
+            BenchmarkId::new(V::VARIANT.name, format!("(u8, u64, {})", start)),
+            |b| b.iter(|| align_to::<V, u8, u64>(bb(slice))),
+        );
+    }
+
+    const N: usize = 4096;
+    let buffer = vec![0u8; N + 8];
+
+    let mut group = c.benchmark_group("align_to");
+    for start in [0usize, 3, 8].iter().copied() {
+        let slice = &buffer[start..start + N];
+        group.bench_function(
+            BenchmarkId::new("core", format!("(u8, u64, {})", start)),
+            |b| b.iter(|| unsafe { bb(slice).align_to::<u64>() }),
+        );
+        for_each_variant_marker!(V => bench_variant::<V>(&mut group, slice, start));
+    }
+    group.finish();
+}
+
 criterion_group! {
     name = benches;
     config = Criterion::default()
         .warm_up_time(core::time::Duration::new(1, 0))
         .sample_size(400);
-    targets = bench_align_offset
+    targets =
+        bench_align_offset,
+        bench_latency_throughput,
+        bench_align_offsets_simd,
+        bench_align_offset_euclid,
+        bench_align_to
 }
 criterion_main!(benches);
@@ -0,0 +1,196 @@
+//! `align_offset` over inputs drawn from realistic distributions, rather than a single input.
+//!
+//! Every group runs the variants over the same pre-generated array of `(p, stride, align)`
+//! inputs, so that the branches taken vary from call to call as they do in real code.
+
+use bench_align_offset::{align_offset_v4, VARIANTS};
+use criterion::{
+    black_box as bb, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput,
+};
+use std::collections::HashMap;
+use std::mem::{align_of, size_of};
+
+/// The arguments `(p, stride, align)` of a single call.
+type Input = (usize, usize, usize);
+
+/// Number of inputs in each distribution.
+const N: usize = 1024;
+
+/// Strides of commonly used types.
+const STRIDES: &[usize] = &[
+    size_of::<u8>(),
+    size_of::<u16>(),
+    size_of::<u32>(),
+    size_of::<u64>(),
+    size_of::<u128>(),
+    size_of::<(u32, u16)>(),
+    size_of::<(u64, u8)>(),
+    size_of::<[u8; 3]>(),
+    size_of::<[u16; 3]>(),
+    size_of::<[u32; 3]>(),
+    size_of::<Box<str>>(),
+    size_of::<String>(),
+    size_of::<Vec<u64>>(),
+    size_of::<Option<String>>(),
+    size_of::<HashMap<u64, u64>>(),
+    size_of::<[u64; 5]>(),
+    size_of::<[u8; 100]>(),
+];
+
+/// Alignments commonly requested: of primitive types, SIMD vectors, cache lines and pages.
+const ALIGNS: &[usize] = &[
+    align_of::<u16>(),
+    align_of::<u32>(),
+    align_of::<u64>(),
+    align_of::<u128>(),
+    16,
+    32,
+    64,
+    4096,
+];
+
+/// A small deterministic PRNG (SplitMix64), so that every run sees the same inputs.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<T: Copy>(&mut self, xs: &[T]) -> T {
+        xs[self.below(xs.len())]
+    }
+}
+
+/// Whether the element of size `stride` at `p` is already aligned to `align`, can be aligned by
+/// moving some elements forward, or can never be aligned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Case {
+    Aligned,
+    Generic,
+    Unalignable,
+}
+
+fn case((p, stride, align): Input) -> Case {
+    match unsafe { align_offset_v4(p, stride, align) } {
+        0 => Case::Aligned,
+        usize::MAX => Case::Unalignable,
+        _ => Case::Generic,
+    }
+}
+
+/// Elements of arrays allocated on the heap, as they would be iterated over.
+///
+/// The allocations are returned along with the inputs, so that they are not reused while
+/// generating.
+fn heap_inputs(rng: &mut Rng) -> (Vec<Input>, Vec<Vec<u8>>) {
+    let mut allocations = Vec::with_capacity(N);
+    let inputs = (0..N)
+        .map(|_| {
+            let stride = rng.pick(STRIDES);
+            let len = 1 + rng.below(16);
+            let allocation = vec![0u8; stride * len];
+            let p = allocation.as_ptr() as usize + stride * rng.below(len);
+            allocations.push(allocation);
+            (p, stride, rng.pick(ALIGNS))
+        })
+        .collect();
+    (inputs, allocations)
+}
+
+/// The address of a local in the `depth`-th nested call, so that it varies with the depth.
+#[inline(never)]
+fn stack_address(depth: usize) -> usize {
+    let local = [0u8; 40];
+    if depth == 0 {
+        bb(&local).as_ptr() as usize
+    } else {
+        bb(stack_address(depth - 1))
+    }
+}
+
+/// Elements of arrays on the stack, at various call depths.
+fn stack_inputs(rng: &mut Rng) -> Vec<Input> {
+    let addresses: Vec<usize> = (0..32).map(stack_address).collect();
+    (0..N)
+        .map(|_| {
+            let stride = rng.pick(STRIDES);
+            let p = rng.pick(&addresses) + stride * rng.below(8);
+            (p, stride, rng.pick(ALIGNS))
+        })
+        .collect()
+}
+
+/// Inputs from `candidates`, of a single `case`.
+fn inputs_of_case(candidates: &[Input], case_: Case, rng: &mut Rng) -> Vec<Input> {
+    let candidates: Vec<_> = candidates
+        .iter()
+        .copied()
+        .filter(|&input| case(input) == case_)
+        .collect();
+    (0..N).map(|_| rng.pick(&candidates)).collect()
+}
+
+/// Arbitrary inputs, with strides and alignments as above, and unaligned pointers.
+fn arbitrary_inputs(rng: &mut Rng) -> Vec<Input> {
+    (0..16 * N)
+        .map(|_| {
+            (
+                (rng.next() >> 16) as usize,
+                rng.pick(STRIDES),
+                rng.pick(ALIGNS),
+            )
+        })
+        .collect()
+}
+
+fn bench_distributions(c: &mut Criterion) {
+    let mut rng = Rng(0x5eed);
+    let (heap, _allocations) = heap_inputs(&mut rng);
+    let stack = stack_inputs(&mut rng);
+    let arbitrary = arbitrary_inputs(&mut rng);
+    let aligned = inputs_of_case(&arbitrary, Case::Aligned, &mut rng);
+    let generic = inputs_of_case(&arbitrary, Case::Generic, &mut rng);
+    let unalignable = inputs_of_case(&arbitrary, Case::Unalignable, &mut rng);
+    let distributions: [(&str, &[Input]); 5] = [
+        ("heap", &heap),
+        ("stack", &stack),
+        ("aligned", &aligned),
+        ("generic", &generic),
+        ("unalignable", &unalignable),
+    ];
+    let mut out = vec![0usize; N];
+
+    let mut group = c.benchmark_group("distributions");
+    group.throughput(Throughput::Elements(N as u64));
+    for (name, inputs) in distributions.iter().copied() {
+        for variant in VARIANTS {
+            group.bench_function(BenchmarkId::new(variant.name, name), |b| {
+                b.iter(|| {
+                    for (&(p, stride, align), o) in bb(inputs).iter().zip(out.iter_mut()) {
+                        *o = unsafe { (variant.func)(p, stride, align) };
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .warm_up_time(core::time::Duration::new(1, 0))
+        .sample_size(400);
+    targets =
+        bench_distributions
+}
+criterion_main!(benches);
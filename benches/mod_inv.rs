@@ -0,0 +1,42 @@
+use bench_align_offset::mod_inv::{
+    mod_pow_2_inv_newton, mod_pow_2_inv_table_16, mod_pow_2_inv_table_256,
+};
+use criterion::{black_box as bb, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+type ModInvFn = fn(usize, u32) -> usize;
+
+const MOD_INV_FNS: [(&str, ModInvFn); 3] = [
+    ("mod_pow_2_inv_table_16", mod_pow_2_inv_table_16),
+    ("mod_pow_2_inv_newton", mod_pow_2_inv_newton),
+    ("mod_pow_2_inv_table_256", mod_pow_2_inv_table_256),
+];
+
+fn bench_mod_inv(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mod_inv");
+    for x in [3usize, 0x9e37_79b9_7f4a_7c15].iter().copied() {
+        for mpow in [4u32, 8, 16, 32, 64].iter().copied() {
+            for (name, f) in MOD_INV_FNS.iter() {
+                group.bench_function(
+                    BenchmarkId::new(*name, format!("({:x}, {})", x, mpow)),
+                    |b| b.iter(|| f(bb(x), bb(mpow))),
+                );
+            }
+            for (name, f) in MOD_INV_FNS.iter() {
+                group.bench_function(
+                    BenchmarkId::new(*name, format!("({:x}, {}*)", x, mpow)),
+                    |b| b.iter(|| f(bb(x), mpow)),
+                );
+            }
+        }
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .warm_up_time(core::time::Duration::new(1, 0))
+        .sample_size(400);
+    targets = bench_mod_inv
+}
+criterion_main!(benches);
@@ -0,0 +1,159 @@
+//! Byte-level alignment of addresses.
+//!
+//! All of these share the mask logic of the variants: for a power-of-two `a`, `a - 1` masks the
+//! address modulo `a`. Only rounding up can overflow, so it comes in a checked and a wrapping form.
+//! Rounding down and the padding are always in range.
+//!
+//! Like the other safe entry points, they all panic if the alignment is not a power of two.
+
+use crate::word::Word;
+
+/// `a - 1`, the mask of the address bits below the alignment.
+///
+/// # Panics
+///
+/// Panics if `a` is not a power of two, with the message of
+/// [`AlignError::NotPowerOfTwo`](crate::checked::AlignError::NotPowerOfTwo).
+#[inline(always)]
+fn a_minus_one<W: Word>(a: W) -> W {
+    assert!(
+        a != W::ZERO && a & a.wrapping_sub(W::ONE) == W::ZERO,
+        "alignment {} is not a power of two",
+        a
+    );
+    a.wrapping_sub(W::ONE)
+}
+
+/// Whether `addr` is a multiple of `a`, which must be a power of two.
+///
+/// # Panics
+///
+/// Panics if `a` is not a power of two.
+#[inline]
+pub fn is_aligned<W: Word>(addr: W, a: W) -> bool {
+    addr & a_minus_one(a) == W::ZERO
+}
+
+/// The number of bytes to add to `addr` to round it up to a multiple of `a`, which must be a power
+/// of two.
+///
+/// This is smaller than `a`, and is also defined when rounding up overflows: adding it to `addr`
+/// then wraps around to 0.
+///
+/// # Panics
+///
+/// Panics if `a` is not a power of two.
+#[inline]
+pub fn padding_needed<W: Word>(addr: W, a: W) -> W {
+    let a_minus_one = a_minus_one(a);
+    a.wrapping_sub(addr & a_minus_one) & a_minus_one
+}
+
+/// Round `addr` down to a multiple of `a`, which must be a power of two.
+///
+/// # Panics
+///
+/// Panics if `a` is not a power of two.
+#[inline]
+pub fn align_down<W: Word>(addr: W, a: W) -> W {
+    addr & !a_minus_one(a)
+}
+
+/// Round `addr` up to a multiple of `a`, which must be a power of two, or `None` if that
+/// multiple does not fit in a `W`.
+///
+/// # Panics
+///
+/// Panics if `a` is not a power of two.
+#[inline]
+pub fn checked_align_up<W: Word>(addr: W, a: W) -> Option<W> {
+    let aligned = wrapping_align_up(addr, a);
+    // Rounding up only ever wraps around to 0, which is then below any unaligned `addr`.
+    if aligned >= addr {
+        Some(aligned)
+    } else {
+        None
+    }
+}
+
+/// Round `addr` up to a multiple of `a`, which must be a power of two, wrapping around to 0 if
+/// that multiple does not fit in a `W`.
+///
+/// # Panics
+///
+/// Panics if `a` is not a power of two.
+#[inline]
+pub fn wrapping_align_up<W: Word>(addr: W, a: W) -> W {
+    let a_minus_one = a_minus_one(a);
+    addr.wrapping_add(a_minus_one) & !a_minus_one
+}
+
+/// Check all the functions against plain arithmetic, for every address and alignment of `$t`.
+#[cfg(test)]
+macro_rules! exhaustive_test {
+    ($name:ident, $t:ty) => {
+        #[test]
+        fn $name() {
+            for addr in 0..=<$t>::MAX {
+                for pow in 0..<$t>::BITS {
+                    let a: $t = 1 << pow;
+                    let down = addr as u128 / a as u128 * a as u128;
+                    let up = (addr as u128).next_multiple_of(a as u128);
+                    let ctx = format!("aligning {:x} to {}", addr, a);
+                    assert_eq!(is_aligned(addr, a), addr as u128 == down, "{}", ctx);
+                    assert_eq!(align_down(addr, a) as u128, down, "{}", ctx);
+                    assert_eq!(
+                        padding_needed(addr, a) as u128,
+                        up - addr as u128,
+                        "{}",
+                        ctx
+                    );
+                    assert_eq!(
+                        checked_align_up(addr, a).map(u128::from),
+                        Some(up).filter(|&up| up <= <$t>::MAX as u128),
+                        "{}",
+                        ctx
+                    );
+                    assert_eq!(wrapping_align_up(addr, a), up as $t, "{}", ctx);
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+exhaustive_test!(exhaustive_u8, u8);
+#[cfg(test)]
+exhaustive_test!(exhaustive_u16, u16);
+
+#[test]
+fn near_usize_max() {
+    let a = 1 << 12;
+    assert_eq!(
+        checked_align_up(usize::MAX - a + 1, a),
+        Some(usize::MAX - a + 1)
+    );
+    assert_eq!(checked_align_up(usize::MAX - a + 2, a), None);
+    assert_eq!(checked_align_up(usize::MAX, 1), Some(usize::MAX));
+    assert_eq!(checked_align_up(usize::MAX, 2), None);
+    assert_eq!(wrapping_align_up(usize::MAX, a), 0);
+    assert_eq!(padding_needed(usize::MAX, a), 1);
+    assert_eq!(align_down(usize::MAX, a), usize::MAX - a + 1);
+    assert!(is_aligned(
+        1usize << (usize::BITS - 1),
+        1 << (usize::BITS - 1)
+    ));
+    assert!(!is_aligned(usize::MAX, 2));
+}
+
+#[test]
+#[should_panic(expected = "alignment 0 is not a power of two")]
+fn padding_needed_zero() {
+    padding_needed(5usize, 0);
+}
+
+#[test]
+#[should_panic(expected = "alignment 24 is not a power of two")]
+fn align_down_not_power_of_two() {
+    align_down(100u32, 24);
+}
@@ -0,0 +1,209 @@
+//! Precomputed `align_offset` for a fixed stride and alignment.
+
+use crate::checked::AlignError;
+use crate::congruence::{Solutions, SolutionsIter};
+use crate::mod_inv::mod_pow_2_inv_table_16;
+use crate::word::Word;
+use core::mem;
+
+/// Everything `align_offset_v4` computes from the stride and the alignment alone, so that finding
+/// the offset of a pointer only takes a couple of ALU operations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Aligner {
+    /// `a - 1`.
+    pub(crate) a_minus_one: usize,
+    /// `s` such that `gcd(stride, a) == 2^s`.
+    pub(crate) gcdpow: u32,
+    /// `gcd(stride, a) - 1`.
+    pub(crate) gcdminus1: usize,
+    /// `a / gcd(stride, a)`.
+    pub(crate) a2: usize,
+    /// `a / gcd(stride, a) - 1`.
+    pub(crate) a2minus1: usize,
+    /// The inverse of `stride / gcd(stride, a)` modulo `a / gcd(stride, a)`.
+    pub(crate) inverse: usize,
+}
+
+impl Aligner {
+    /// Precompute offsets for elements of size `stride` and the alignment `a`.
+    pub fn new(stride: usize, a: usize) -> Result<Self, AlignError> {
+        if !a.is_power_of_two() {
+            return Err(AlignError::NotPowerOfTwo(a));
+        }
+        // SAFETY: `a` was checked to be a power of two above.
+        Ok(unsafe { Self::new_unchecked(stride, a) })
+    }
+
+    /// Precompute offsets for elements of size `stride` and the alignment `a`, without checking
+    /// `a`.
+    ///
+    /// # Safety
+    ///
+    /// `a` must be a power of two.
+    #[inline]
+    pub unsafe fn new_unchecked(stride: usize, a: usize) -> Self {
+        let a_minus_one = a.wrapping_sub(1);
+        // a is power-of-two so cannot be 0. For a zero stride `trailing_zeros` is `usize::BITS`,
+        // and so $g = a$.
+        let apow = a.cttz_nonzero();
+        let gcdpow = stride.trailing_zeros().min(apow);
+        let a2 = a >> gcdpow;
+        let s2 = (stride & a_minus_one) >> gcdpow;
+        Aligner {
+            a_minus_one,
+            gcdpow,
+            gcdminus1: (1usize << gcdpow).wrapping_sub(1),
+            a2,
+            a2minus1: a2.wrapping_sub(1),
+            // If $a' = 1$, `s2` may be even, but then the result is reduced modulo 1 anyway.
+            inverse: mod_pow_2_inv_table_16(s2, apow - gcdpow),
+        }
+    }
+
+    /// Whether `p` can be aligned by offsetting it by whole elements, i.e. it is divisible by
+    /// `gcd(stride, a)`.
+    #[inline]
+    pub fn can_align(&self, p: usize) -> bool {
+        p & self.gcdminus1 == 0
+    }
+
+    /// Whether any pointer which is not already aligned can be aligned by offsetting it by whole
+    /// elements. This is not the case for zero-sized elements, or whenever the stride is a
+    /// multiple of `a`.
+    #[inline]
+    pub fn can_ever_align(&self) -> bool {
+        self.a2minus1 != 0
+    }
+
+    /// Calculate the offset, in elements, that needs to be applied to the address `p` for it to
+    /// become aligned, or `usize::MAX` if no such offset exists.
+    #[inline]
+    pub fn offset(&self, p: usize) -> usize {
+        // See `align_offset_v4` for the derivation. The special cases of an already aligned
+        // pointer, and of a stride of 0 or 1, all fall out of the general solution.
+        if self.can_align(p) {
+            let minusp2 = self.a2.wrapping_sub((p & self.a_minus_one) >> self.gcdpow);
+            minusp2.wrapping_mul(self.inverse) & self.a2minus1
+        } else {
+            // Cannot be aligned at all.
+            usize::MAX
+        }
+    }
+
+    /// All the offsets, in elements, that align the address `p`, or `None` if there are none.
+    ///
+    /// These start at [`offset`](Self::offset), and repeat every `a / gcd(stride, a)` elements.
+    #[inline]
+    pub fn solutions(&self, p: usize) -> Option<Solutions> {
+        if self.can_align(p) {
+            Some(Solutions {
+                first: self.offset(p),
+                period: self.a2,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the indices, below `len`, of all the elements of a slice starting at the
+    /// address `p` which are aligned.
+    #[inline]
+    pub fn aligned_indices(&self, p: usize, len: usize) -> AlignedIndices {
+        self.solutions(p)
+            .map_or_else(AlignedIndices::default, |solutions| solutions.below(len))
+    }
+}
+
+/// Iterator over the aligned element indices of a slice, created by
+/// [`Aligner::aligned_indices`] and [`aligned_indices`].
+pub type AlignedIndices = SolutionsIter;
+
+/// Iterate over the indices of all the elements of `slice` which are aligned to `a`.
+pub fn aligned_indices<T>(slice: &[T], a: usize) -> Result<AlignedIndices, AlignError> {
+    let aligner = Aligner::new(mem::size_of::<T>(), a)?;
+    Ok(aligner.aligned_indices(slice.as_ptr() as usize, slice.len()))
+}
+
+#[test]
+fn aligner_weird_strides() {
+    // For pointers of stride != 1, we verify the algorithm against the naivest possible
+    // implementation
+    let mut align = 1;
+    let mut x = false;
+    while align < 1024 {
+        for stride in 0..11 {
+            let aligner = Aligner::new(stride, align).unwrap();
+            assert_eq!(aligner.can_ever_align(), stride % align != 0);
+            for ptr in 1usize..4 * align {
+                let expected = crate::naive_align_offset(ptr, stride, align);
+                let got = aligner.offset(ptr);
+                if got != expected {
+                    eprintln!(
+                        "Aligner: aligning {:x} (with stride of {}) to {}, expected {}, got {}",
+                        ptr, stride, align, expected, got
+                    );
+                    x = true;
+                }
+                assert_eq!(aligner.can_align(ptr), expected != usize::MAX);
+            }
+        }
+        align = (align + 1).next_power_of_two();
+    }
+    assert!(!x);
+    assert_eq!(Aligner::new(24, 24), Err(AlignError::NotPowerOfTwo(24)));
+}
+
+#[test]
+fn aligned_indices_brute_force() {
+    let mut align = 1;
+    while align <= 256 {
+        for stride in [0usize, 1, 2, 3, 4, 6, 8, 12, 24, 100, 256, 1024]
+            .iter()
+            .copied()
+        {
+            let aligner = Aligner::new(stride, align).unwrap();
+            for ptr in 0usize..2 * align {
+                for len in [0usize, 1, 7, 300].iter().copied() {
+                    let expected: Vec<usize> = (0..len)
+                        .filter(|&i| (ptr + i * stride).is_multiple_of(align))
+                        .collect();
+                    let indices = aligner.aligned_indices(ptr, len);
+                    assert_eq!(indices.len(), expected.len());
+                    assert_eq!(
+                        indices.collect::<Vec<_>>(),
+                        expected,
+                        "aligning {:x} (with stride of {}) to {}, below {}",
+                        ptr,
+                        stride,
+                        align,
+                        len
+                    );
+                }
+            }
+        }
+        align <<= 1;
+    }
+    // Indices into an actual slice.
+    let buf = [0u32; 64];
+    let slice = &buf[1..];
+    for align in [1usize, 2, 4, 8, 16, 32, 64].iter().copied() {
+        let expected: Vec<usize> = (0..slice.len())
+            .filter(|&i| (&slice[i] as *const u32 as usize).is_multiple_of(align))
+            .collect();
+        assert_eq!(
+            aligned_indices(slice, align).unwrap().collect::<Vec<_>>(),
+            expected
+        );
+    }
+    assert_eq!(
+        aligned_indices(slice, 12).unwrap_err(),
+        AlignError::NotPowerOfTwo(12)
+    );
+    // Stepping past the end of the address space stops the iteration.
+    let quarter = 1usize << (usize::BITS - 2);
+    let aligner = Aligner::new(1, quarter).unwrap();
+    assert_eq!(
+        aligner.aligned_indices(1, usize::MAX).collect::<Vec<_>>(),
+        [quarter - 1, 2 * quarter - 1, 3 * quarter - 1]
+    );
+}
@@ -0,0 +1,50 @@
+//! Computing `align_offset` for many pointers sharing the same stride and alignment.
+
+use crate::aligner::Aligner;
+
+/// Calculate, for every address in `ptrs`, the offset in elements of size `stride` that needs to
+/// be applied to it for it to become aligned to `a`, or `usize::MAX` if no such offset exists,
+/// writing the offsets into `out`.
+///
+/// Everything that depends only on `stride` and `a` is computed once, in an [`Aligner`], leaving
+/// a handful of ALU operations per address.
+///
+/// # Panics
+///
+/// Panics if `ptrs` and `out` have different lengths.
+///
+/// # Safety
+///
+/// `a` must be a power of two.
+pub unsafe fn align_offsets(ptrs: &[usize], stride: usize, a: usize, out: &mut [usize]) {
+    assert_eq!(
+        ptrs.len(),
+        out.len(),
+        "ptrs and out must have the same length"
+    );
+
+    let aligner = Aligner::new_unchecked(stride, a);
+    for (&p, o) in ptrs.iter().zip(out.iter_mut()) {
+        *o = aligner.offset(p);
+    }
+}
+
+#[test]
+fn align_offsets_matches_v4() {
+    let mut out = [0; 4096];
+    for apow in 0..usize::BITS {
+        let align = 1usize << apow;
+        let ptrs: Vec<usize> = crate::test_ptrs(align, 2048).collect();
+        for stride in crate::test_strides() {
+            unsafe { align_offsets(&ptrs, stride, align, &mut out) };
+            for (&ptr, &got) in ptrs.iter().zip(out.iter()) {
+                let expected = unsafe { crate::align_offset_v4(ptr, stride, align) };
+                assert_eq!(
+                    got, expected,
+                    "align_offsets: aligning {:x} (with stride of {}) to {}",
+                    ptr, stride, align
+                );
+            }
+        }
+    }
+}
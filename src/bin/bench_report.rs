@@ -0,0 +1,460 @@
+//! Collect the criterion results of the benchmarks into a matrix of variant × input.
+//!
+//! ```text
+//! cargo bench
+//! cargo run --features report --bin bench_report -- [--dir target/criterion]
+//!     [--baseline align_offset_v0] [--format markdown|csv|csv-long]
+//! ```
+//!
+//! Every benchmark group gets its own matrix, where every input is a row, and every variant (the
+//! function of the benchmark ID) measured in that group a column. Each cell holds the mean time
+//! and the speedup over the baseline variant on the same input. The time is per element for the
+//! groups with an element throughput, such as `latency` and `throughput`, and per iteration
+//! otherwise.
+//!
+//! The CSV output has the same matrices, one block per group. The long CSV output has one line
+//! per measurement instead, so that all the groups share a header.
+
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// A single argument of a benchmark input, e.g. `24*`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Arg {
+    value: u128,
+    /// Whether the argument was a constant, marked by a trailing `*`, rather than opaque.
+    constant: bool,
+}
+
+impl fmt::Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, if self.constant { "*" } else { "" })
+    }
+}
+
+/// The input of a benchmark, parsed from its parameter.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Input {
+    /// A tuple of numbers such as `(8, 24*, 4096)`: `(p, stride, align)`, or `(stride, align)`.
+    Args(Vec<Arg>),
+    /// Any other parameter, such as the name of an input distribution.
+    Other(String),
+}
+
+impl Input {
+    fn parse(s: &str) -> Input {
+        let args = s
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .and_then(|s| {
+                s.split(',')
+                    .map(|arg| {
+                        let arg = arg.trim();
+                        let value = arg.strip_suffix('*').unwrap_or(arg);
+                        Some(Arg {
+                            value: value.parse().ok()?,
+                            constant: value.len() != arg.len(),
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()
+            });
+        match args {
+            Some(args) => Input::Args(args),
+            None => Input::Other(s.to_string()),
+        }
+    }
+
+    /// The arguments named `p`, `stride` and `align`, where present.
+    fn named_args(&self) -> [Option<&Arg>; 3] {
+        match self {
+            Input::Args(args) => match &args[..] {
+                [p, stride, align] => [Some(p), Some(stride), Some(align)],
+                [stride, align] => [None, Some(stride), Some(align)],
+                _ => [None; 3],
+            },
+            Input::Other(_) => [None; 3],
+        }
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Args(args) => {
+                let args: Vec<_> = args.iter().map(Arg::to_string).collect();
+                write!(f, "({})", args.join(", "))
+            }
+            Input::Other(s) => f.write_str(s),
+        }
+    }
+}
+
+/// The result of a single benchmark.
+#[derive(Clone, Debug, PartialEq)]
+struct Measurement {
+    group: String,
+    variant: String,
+    input: Input,
+    /// Mean time in nanoseconds, per element if `per_element`, and per iteration otherwise.
+    mean_ns: f64,
+    /// Whether the benchmark has an element throughput, which `mean_ns` was divided by.
+    per_element: bool,
+}
+
+/// Read the measurement in a criterion benchmark directory, holding `benchmark.json` and
+/// `estimates.json`.
+fn read_measurement(dir: &Path) -> Result<Measurement, String> {
+    let read = |name: &str| {
+        let path = dir.join(name);
+        fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|json| serde_json::from_str::<Value>(&json).map_err(|err| err.to_string()))
+            .map_err(|err| format!("{}: {}", path.display(), err))
+    };
+    let benchmark = read("benchmark.json")?;
+    let estimates = read("estimates.json")?;
+    let field = |key: &str| {
+        benchmark[key]
+            .as_str()
+            .ok_or_else(|| format!("{}: no `{}`", dir.display(), key))
+    };
+    let mean_ns = estimates["mean"]["point_estimate"]
+        .as_f64()
+        .ok_or_else(|| format!("{}: no mean estimate", dir.display()))?;
+    // Byte throughputs are left alone, as the time per byte is not comparable across inputs.
+    let elements = benchmark["throughput"]["Elements"].as_u64();
+    Ok(Measurement {
+        group: field("group_id")?.to_string(),
+        variant: field("function_id")?.to_string(),
+        input: Input::parse(benchmark["value_str"].as_str().unwrap_or("")),
+        mean_ns: mean_ns / elements.unwrap_or(1) as f64,
+        per_element: elements.is_some(),
+    })
+}
+
+/// Find all the latest measurements under the criterion output directory.
+fn collect_measurements(dir: &Path, out: &mut Vec<Measurement>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+    for entry in entries {
+        let path = entry.map_err(|err| err.to_string())?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if path.file_name().is_some_and(|name| name == "new")
+            && path.join("benchmark.json").is_file()
+        {
+            out.push(read_measurement(&path)?);
+        } else {
+            collect_measurements(&path, out)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Markdown,
+    Csv,
+    CsvLong,
+}
+
+/// The measurements of one benchmark group, arranged by input rows and variant columns.
+#[derive(Default)]
+struct Group {
+    /// The variants measured on any input of the group.
+    variants: Vec<String>,
+    rows: BTreeMap<Input, BTreeMap<String, f64>>,
+    /// Whether the times are per element rather than per iteration.
+    per_element: bool,
+}
+
+impl Group {
+    /// What the times are per, `element` or `iteration`.
+    fn per(&self) -> &'static str {
+        if self.per_element {
+            "element"
+        } else {
+            "iteration"
+        }
+    }
+}
+
+/// The measurements arranged by group.
+struct Matrix {
+    groups: BTreeMap<String, Group>,
+    baseline: String,
+}
+
+impl Matrix {
+    fn new(measurements: Vec<Measurement>, baseline: &str) -> Matrix {
+        let mut variants: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        let mut groups: BTreeMap<_, Group> = BTreeMap::new();
+        for m in measurements {
+            variants
+                .entry(m.group.clone())
+                .or_default()
+                .insert(m.variant.clone());
+            let group = groups.entry(m.group).or_default();
+            group.per_element |= m.per_element;
+            group
+                .rows
+                .entry(m.input)
+                .or_default()
+                .insert(m.variant, m.mean_ns);
+        }
+        for (name, variants) in variants {
+            groups.get_mut(&name).unwrap().variants = variants.into_iter().collect();
+        }
+        Matrix {
+            groups,
+            baseline: baseline.to_string(),
+        }
+    }
+
+    /// The speedup of `variant` over the baseline in a row, if both were measured.
+    fn speedup(&self, row: &BTreeMap<String, f64>, variant: &str) -> Option<f64> {
+        Some(row.get(&self.baseline)? / row.get(variant)?)
+    }
+
+    fn write_markdown(&self, out: &mut String) {
+        for (name, group) in &self.groups {
+            out.push_str(&format!(
+                "\n## {} (ns per {})\n\n| input |",
+                name,
+                group.per()
+            ));
+            for variant in &group.variants {
+                out.push_str(&format!(" {} |", variant));
+            }
+            out.push_str("\n|---|");
+            out.push_str(&"---:|".repeat(group.variants.len()));
+            out.push('\n');
+            for (input, row) in &group.rows {
+                out.push_str(&format!("| {} |", input));
+                for variant in &group.variants {
+                    match (row.get(variant), self.speedup(row, variant)) {
+                        (Some(ns), Some(speedup)) => {
+                            out.push_str(&format!(" {:.2} ({:.2}×) |", ns, speedup))
+                        }
+                        (Some(ns), None) => out.push_str(&format!(" {:.2} |", ns)),
+                        (None, _) => out.push_str(" |"),
+                    }
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    /// One CSV block per group, separated by empty lines, with a row per input and a pair of
+    /// columns per variant, like the Markdown tables.
+    fn write_csv(&self, out: &mut String) {
+        for (i, (name, group)) in self.groups.iter().enumerate() {
+            if i != 0 {
+                out.push('\n');
+            }
+            out.push_str("group,input,p,stride,align,constant");
+            for variant in &group.variants {
+                out.push_str(&format!(
+                    ",{0}_ns_per_{1},{0}_speedup",
+                    variant,
+                    group.per()
+                ));
+            }
+            out.push('\n');
+            for (input, row) in &group.rows {
+                out.push_str(&format!("{},{}", name, input_columns(input)));
+                for variant in &group.variants {
+                    let ns = row.get(variant).map_or(String::new(), |ns| ns.to_string());
+                    let speedup = self
+                        .speedup(row, variant)
+                        .map_or(String::new(), |speedup| speedup.to_string());
+                    out.push_str(&format!(",{},{}", ns, speedup));
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    /// A single CSV table with one row per measurement, which is easier to aggregate.
+    fn write_csv_long(&self, out: &mut String) {
+        out.push_str("group,input,p,stride,align,constant,variant,ns,per,speedup\n");
+        for (name, group) in &self.groups {
+            for (input, row) in &group.rows {
+                for (variant, ns) in row {
+                    let speedup = self
+                        .speedup(row, variant)
+                        .map_or(String::new(), |speedup| speedup.to_string());
+                    out.push_str(&format!(
+                        "{},{},{},{},{},{}\n",
+                        name,
+                        input_columns(input),
+                        variant,
+                        ns,
+                        group.per(),
+                        speedup
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// The CSV columns `input,p,stride,align,constant` of an input.
+fn input_columns(input: &Input) -> String {
+    let named = input.named_args();
+    let constant: Vec<_> = ["p", "stride", "align"]
+        .iter()
+        .zip(named.iter())
+        .filter(|(_, arg)| arg.is_some_and(|arg| arg.constant))
+        .map(|(name, _)| *name)
+        .collect();
+    let mut columns = format!("\"{}\"", input);
+    for arg in named.iter() {
+        let value = arg.map_or(String::new(), |arg| arg.value.to_string());
+        columns.push_str(&format!(",{}", value));
+    }
+    columns.push_str(&format!(",{}", constant.join(" ")));
+    columns
+}
+
+const USAGE: &str = "usage: bench_report [--dir <criterion dir>] [--baseline <variant>] \
+                     [--format markdown|csv|csv-long]";
+
+fn run() -> Result<String, String> {
+    let mut dir = PathBuf::from("target/criterion");
+    let mut baseline = "align_offset_v0".to_string();
+    let mut format = Format::Markdown;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match &arg[..] {
+            "--dir" => dir = PathBuf::from(value()?),
+            "--baseline" => baseline = value()?,
+            "--format" => {
+                format = match &value()?[..] {
+                    "markdown" | "md" => Format::Markdown,
+                    "csv" => Format::Csv,
+                    "csv-long" => Format::CsvLong,
+                    other => return Err(format!("unknown format `{}`", other)),
+                }
+            }
+            "-h" | "--help" => return Ok(format!("{}\n", USAGE)),
+            _ => return Err(format!("unexpected argument `{}`\n{}", arg, USAGE)),
+        }
+    }
+
+    let mut measurements = Vec::new();
+    collect_measurements(&dir, &mut measurements)?;
+    if !measurements.iter().any(|m| m.variant == baseline) {
+        return Err(format!("no measurements of the baseline `{}`", baseline));
+    }
+    let matrix = Matrix::new(measurements, &baseline);
+    let mut out = String::new();
+    match format {
+        Format::Markdown => matrix.write_markdown(&mut out),
+        Format::Csv => matrix.write_csv(&mut out),
+        Format::CsvLong => matrix.write_csv_long(&mut out),
+    }
+    Ok(out)
+}
+
+fn main() {
+    match run() {
+        Ok(out) => print!("{}", out),
+        Err(err) => {
+            eprintln!("bench_report: {}", err);
+            process::exit(2);
+        }
+    }
+}
+
+#[test]
+fn parse_input() {
+    let arg = |value, constant| Arg { value, constant };
+    let input = Input::parse("(8, 24*, 4096)");
+    assert_eq!(
+        input,
+        Input::Args(vec![arg(8, false), arg(24, true), arg(4096, false)])
+    );
+    assert_eq!(input.to_string(), "(8, 24*, 4096)");
+    assert_eq!(
+        input.named_args(),
+        [
+            Some(&arg(8, false)),
+            Some(&arg(24, true)),
+            Some(&arg(4096, false))
+        ]
+    );
+    assert_eq!(
+        Input::parse("(24, 16*)").named_args(),
+        [None, Some(&arg(24, false)), Some(&arg(16, true))]
+    );
+    assert_eq!(Input::parse("heap"), Input::Other("heap".to_string()));
+    assert_eq!(Input::parse("()"), Input::Other("()".to_string()));
+    assert_eq!(
+        Input::parse("(u8, u64, 3)"),
+        Input::Other("(u8, u64, 3)".to_string())
+    );
+}
+
+#[test]
+fn matrix_speedups() {
+    let measurement = |group: &str, variant: &str, input: &str, mean_ns| Measurement {
+        group: group.to_string(),
+        variant: variant.to_string(),
+        input: Input::parse(input),
+        mean_ns,
+        per_element: group == "batch",
+    };
+    let matrix = Matrix::new(
+        vec![
+            measurement("args", "v0", "(8, 24*, 16)", 4.0),
+            measurement("args", "v1", "(8, 24*, 16)", 2.0),
+            measurement("args", "v1", "(8, 24, 16)", 3.0),
+            measurement("batch", "v0", "(24, 16)", 0.5),
+            measurement("batch", "v2", "(24, 16)", 0.25),
+        ],
+        "v0",
+    );
+
+    let mut markdown = String::new();
+    matrix.write_markdown(&mut markdown);
+    assert_eq!(
+        markdown,
+        "\n## args (ns per iteration)\n\n| input | v0 | v1 |\n|---|---:|---:|\n\
+         | (8, 24, 16) | | 3.00 |\n\
+         | (8, 24*, 16) | 4.00 (1.00×) | 2.00 (2.00×) |\n\
+         \n## batch (ns per element)\n\n| input | v0 | v2 |\n|---|---:|---:|\n\
+         | (24, 16) | 0.50 (1.00×) | 0.25 (2.00×) |\n"
+    );
+
+    let mut csv = String::new();
+    matrix.write_csv(&mut csv);
+    assert_eq!(
+        csv,
+        "group,input,p,stride,align,constant,\
+         v0_ns_per_iteration,v0_speedup,v1_ns_per_iteration,v1_speedup\n\
+         args,\"(8, 24, 16)\",8,24,16,,,,3,\n\
+         args,\"(8, 24*, 16)\",8,24,16,stride,4,1,2,2\n\
+         \n\
+         group,input,p,stride,align,constant,\
+         v0_ns_per_element,v0_speedup,v2_ns_per_element,v2_speedup\n\
+         batch,\"(24, 16)\",,24,16,,0.5,1,0.25,2\n"
+    );
+
+    let mut csv = String::new();
+    matrix.write_csv_long(&mut csv);
+    assert_eq!(
+        csv,
+        "group,input,p,stride,align,constant,variant,ns,per,speedup\n\
+         args,\"(8, 24, 16)\",8,24,16,,v1,3,iteration,\n\
+         args,\"(8, 24*, 16)\",8,24,16,stride,v0,4,iteration,1\n\
+         args,\"(8, 24*, 16)\",8,24,16,stride,v1,2,iteration,2\n\
+         batch,\"(24, 16)\",,24,16,,v0,0.5,element,1\n\
+         batch,\"(24, 16)\",,24,16,,v2,0.25,element,2\n"
+    );
+}
@@ -0,0 +1,92 @@
+//! A safe entry point to the variants, validating their preconditions.
+
+use crate::registry::Variant;
+use core::fmt;
+
+/// Reasons for which [`align_offset`] cannot produce an offset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AlignError {
+    /// The requested alignment is not a power of two.
+    NotPowerOfTwo(usize),
+    /// The requested alignment is larger than the chosen variant supports.
+    UnsupportedAlignment(usize),
+    /// The pointer is not aligned, and the stride is zero, so no amount of elements will ever
+    /// align it.
+    ZeroSizedStride,
+}
+
+impl fmt::Display for AlignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignError::NotPowerOfTwo(a) => write!(f, "alignment {} is not a power of two", a),
+            AlignError::UnsupportedAlignment(a) => {
+                write!(f, "alignment {} is not supported by this variant", a)
+            }
+            AlignError::ZeroSizedStride => {
+                write!(
+                    f,
+                    "unaligned pointer cannot be aligned with a zero-sized stride"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for AlignError {}
+
+/// Calculate the offset, in elements of size `stride`, that needs to be applied to the address `p`
+/// for it to become aligned to `a`, using the given `variant`.
+///
+/// Returns `Ok(None)` if no such offset exists, i.e. `p` is not a multiple of `gcd(stride, a)`.
+pub fn align_offset(
+    variant: &Variant,
+    p: usize,
+    stride: usize,
+    a: usize,
+) -> Result<Option<usize>, AlignError> {
+    if !a.is_power_of_two() {
+        return Err(AlignError::NotPowerOfTwo(a));
+    }
+    if a > variant.max_align() {
+        return Err(AlignError::UnsupportedAlignment(a));
+    }
+    // SAFETY: `a` was checked to be a power of two above, which is all the variants require.
+    let offset = unsafe { (variant.func)(p, stride, a) };
+    if offset != usize::MAX {
+        // An offset is always smaller than `a`, so it can never collide with `usize::MAX`.
+        Ok(Some(offset))
+    } else if stride == 0 {
+        Err(AlignError::ZeroSizedStride)
+    } else {
+        Ok(None)
+    }
+}
+
+#[test]
+fn align_offset_checked() {
+    for variant in crate::VARIANTS {
+        assert_eq!(align_offset(variant, 8, 24, 16), Ok(Some(1)));
+        assert_eq!(align_offset(variant, 16, 24, 16), Ok(Some(0)));
+        assert_eq!(align_offset(variant, 3, 1, 8), Ok(Some(5)));
+        assert_eq!(align_offset(variant, 4, 24, 16), Ok(None));
+        assert_eq!(align_offset(variant, 16, 0, 16), Ok(Some(0)));
+        assert_eq!(
+            align_offset(variant, 8, 0, 16),
+            Err(AlignError::ZeroSizedStride)
+        );
+        assert_eq!(
+            align_offset(variant, 8, 24, 0),
+            Err(AlignError::NotPowerOfTwo(0))
+        );
+        assert_eq!(
+            align_offset(variant, 8, 24, 24),
+            Err(AlignError::NotPowerOfTwo(24))
+        );
+        if let Some(too_large) = variant.max_align::<usize>().checked_mul(2) {
+            assert_eq!(
+                align_offset(variant, 8, 24, too_large),
+                Err(AlignError::UnsupportedAlignment(too_large))
+            );
+        }
+    }
+}
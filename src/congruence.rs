@@ -0,0 +1,191 @@
+//! Solving general linear congruences $ax ≡ b (mod m)$.
+//!
+//! `align_offset` only asks for the smallest solution of $p + so ≡ 0 (mod a)$, which is the
+//! special case $s·o ≡ -p (mod a)$. Here we return the whole solution set instead, so that e.g.
+//! all the aligned element indices can be enumerated, not only the first one.
+
+use crate::euclid::ext_gcd;
+use crate::mod_inv::mod_pow_2_inv_table_16;
+
+/// The solutions of a linear congruence: all `x` such that `x ≡ first (mod period)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Solutions {
+    /// The smallest solution, which is smaller than `period`.
+    pub first: usize,
+    /// The difference between consecutive solutions, `m / gcd(a, m)`.
+    pub period: usize,
+}
+
+impl Solutions {
+    /// Whether `x` is a solution.
+    pub fn contains(&self, x: usize) -> bool {
+        x % self.period == self.first
+    }
+
+    /// All the solutions in increasing order, below `usize::MAX`.
+    ///
+    /// `usize::MAX` itself is left out so that their number always fits in a `usize`.
+    pub fn iter(&self) -> SolutionsIter {
+        self.below(usize::MAX)
+    }
+
+    /// All the solutions in increasing order, below `end`.
+    pub fn below(&self, end: usize) -> SolutionsIter {
+        SolutionsIter {
+            next: self.first,
+            end,
+            period: self.period,
+        }
+    }
+}
+
+impl IntoIterator for Solutions {
+    type Item = usize;
+    type IntoIter = SolutionsIter;
+
+    fn into_iter(self) -> SolutionsIter {
+        self.iter()
+    }
+}
+
+/// Iterator over [`Solutions`] below some bound, in increasing order.
+///
+/// The default iterator is empty.
+#[derive(Clone, Debug, Default)]
+pub struct SolutionsIter {
+    /// The next solution, done once it is not below `end`.
+    next: usize,
+    /// The bound on the solutions.
+    end: usize,
+    /// The difference between consecutive solutions.
+    period: usize,
+}
+
+impl Iterator for SolutionsIter {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.next >= self.end {
+            return None;
+        }
+        let x = self.next;
+        self.next = x.checked_add(self.period).unwrap_or(self.end);
+        Some(x)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = if self.next >= self.end {
+            0
+        } else {
+            (self.end - self.next - 1) / self.period + 1
+        };
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for SolutionsIter {}
+
+/// Solve $ax ≡ b (mod m)$, returning `None` if there are no solutions.
+///
+/// Power-of-two moduli take the same fast path as `align_offset_v4`, all others are solved with
+/// the extended Euclidean algorithm.
+///
+/// # Panics
+///
+/// Panics if `m` is zero.
+pub fn solve(a: usize, b: usize, m: usize) -> Option<Solutions> {
+    assert_ne!(m, 0, "the modulus must not be zero");
+
+    // With $g = gcd(a, m)$, there is only a solution if $g$ also divides $b$, in which case, with
+    // $m' = m/g$, $a' = a/g$, $b' = b/g$ it becomes equivalent to:
+    //
+    // $$ a'x ≡ b' (mod m') $$
+    // $$ x = b' * ((a')⁻¹ mod m') mod m' $$
+    //
+    // and all solutions are congruent modulo $m'$.
+    if m.is_power_of_two() {
+        let m_minus_one = m - 1;
+        let mpow = m.trailing_zeros();
+        // For $a = 0$, `trailing_zeros` is `usize::BITS`, and so $g = m$.
+        let gcdpow = a.trailing_zeros().min(mpow);
+        let bmodm = b & m_minus_one;
+        if bmodm & ((1usize << gcdpow) - 1) != 0 {
+            return None;
+        }
+        let period = m >> gcdpow;
+        let inverse = mod_pow_2_inv_table_16((a & m_minus_one) >> gcdpow, mpow - gcdpow);
+        let first = (bmodm >> gcdpow).wrapping_mul(inverse) & (period - 1);
+        Some(Solutions { first, period })
+    } else {
+        let (gcd, inverse) = ext_gcd(a % m, m);
+        let bmodm = b % m;
+        if !bmodm.is_multiple_of(gcd) {
+            return None;
+        }
+        let period = m / gcd;
+        // The product may overflow a `usize`, so it is taken in double width.
+        let first = ((bmodm / gcd) as u128 * (inverse % period) as u128 % period as u128) as usize;
+        Some(Solutions { first, period })
+    }
+}
+
+/// All the offsets, in elements of size `stride`, that can be applied to the address `p` for it to
+/// become divisible by `m`, or `None` if there are none.
+///
+/// # Panics
+///
+/// Panics if `m` is zero.
+pub fn aligned_offsets(p: usize, stride: usize, m: usize) -> Option<Solutions> {
+    // $$ p + so ≡ 0 (mod m) ⟺ so ≡ -p (mod m) $$
+    solve(stride, (m - p % m) % m, m)
+}
+
+#[test]
+fn solve_brute_force() {
+    for m in 1..70 {
+        for a in 0..2 * m + 3 {
+            for b in 0..2 * m {
+                let brute: Vec<usize> = (0..3 * m).filter(|x| (a * x) % m == b % m).collect();
+                match solve(a, b, m) {
+                    None => assert!(brute.is_empty(), "{}x ≡ {} (mod {})", a, b, m),
+                    Some(solutions) => {
+                        assert!(solutions.first < solutions.period);
+                        let all: Vec<usize> = solutions.iter().take_while(|&x| x < 3 * m).collect();
+                        assert_eq!(all, brute, "{}x ≡ {} (mod {})", a, b, m);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn aligned_offsets_matches_align_offset() {
+    for m in 1..300 {
+        for stride in [0usize, 1, 3, 8, 24, 100, 4096, usize::MAX].iter().copied() {
+            for p in (0..64).chain((0..64).map(|i| usize::MAX - i)) {
+                let first = aligned_offsets(p, stride, m).map_or(usize::MAX, |s| s.first);
+                assert_eq!(first, crate::align_offset_euclid(p, stride, m));
+                if m.is_power_of_two() {
+                    assert_eq!(first, unsafe { crate::align_offset_v4(p, stride, m) });
+                }
+            }
+        }
+    }
+    // The solutions stop before overflowing.
+    let quarter = 1usize << (usize::BITS - 2);
+    let solutions = solve(1, 1, quarter).unwrap();
+    assert_eq!(solutions.iter().len(), 4);
+    assert_eq!(
+        solutions.iter().collect::<Vec<_>>(),
+        [1, quarter + 1, 2 * quarter + 1, 3 * quarter + 1]
+    );
+    assert_eq!(
+        solutions.below(2 * quarter + 1).collect::<Vec<_>>(),
+        [1, quarter + 1]
+    );
+    // Every `usize` but `usize::MAX`.
+    assert_eq!(solve(1, 0, 1).unwrap().iter().len(), usize::MAX);
+}
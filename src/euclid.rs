@@ -0,0 +1,111 @@
+//! `align_offset` for arbitrary, not necessarily power-of-two, moduli, using the extended
+//! Euclidean algorithm.
+
+/// Calculate `gcd(x, m)`, and `y` in `[0, m)` such that $xy ≡ gcd(x, m) (mod m)$, using the
+/// extended Euclidean algorithm. `y` is then the inverse of `x / gcd` modulo `m / gcd`.
+///
+/// The Bézout coefficients never exceed `m` in absolute value, so they fit in an `i128`.
+pub(crate) fn ext_gcd(x: usize, m: usize) -> (usize, usize) {
+    let (mut old_r, mut r) = (x, m);
+    let (mut old_y, mut y) = (1i128, 0i128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_y, y) = (y, old_y - q as i128 * y);
+    }
+    (old_r, old_y.rem_euclid(m as i128) as usize)
+}
+
+/// Calculate the offset, in elements of size `stride`, that needs to be applied to the address `p`
+/// for it to become divisible by `m`, or `usize::MAX` if no such offset exists.
+///
+/// Unlike the other variants, `m` may be any modulus, e.g. a cache-set or page-colouring stride.
+///
+/// # Panics
+///
+/// Panics if `m` is zero.
+pub fn align_offset_euclid(p: usize, stride: usize, m: usize) -> usize {
+    let pmodm = p % m;
+    if pmodm == 0 {
+        // Already aligned. Yay!
+        return 0;
+    }
+
+    // This solves for the following linear congruence equation:
+    //
+    // $$ p + so ≡ 0 mod m $$
+    //
+    // With $g = gcd(s, m)$, it only has a solution if $g$ also divides $p$, in which case, with
+    // $m' = m/g$, $s' = s/g$, $p' = p/g$ it becomes equivalent to:
+    //
+    // $$ p' + s'o ≡ 0 mod m' $$
+    // $$ o = (m' - (p' mod m')) * ((s')⁻¹ mod m') mod m' $$
+    //
+    // which is minimal, as all solutions are congruent modulo $m'$. If $s ≡ 0 (mod m)$, then
+    // $g = m$, which cannot divide $p$ here, so this is handled as well.
+    let (gcd, inverse) = ext_gcd(stride % m, m);
+    let minusp = m - pmodm;
+    if !minusp.is_multiple_of(gcd) {
+        // Cannot be aligned at all.
+        return usize::MAX;
+    }
+    let m2 = m / gcd;
+    // The product may overflow a `usize`, so it is taken in double width.
+    ((minusp / gcd) as u128 * (inverse % m2) as u128 % m2 as u128) as usize
+}
+
+#[test]
+fn align_offset_euclid_weird_moduli() {
+    let mut x = false;
+    for m in 1..64 {
+        for stride in 0..2 * m + 3 {
+            for ptr in 0..2 * m {
+                let expected = crate::naive_align_offset(ptr, stride, m);
+                let got = align_offset_euclid(ptr, stride, m);
+                if got != expected {
+                    eprintln!(
+                        "align_offset_euclid: aligning {:x} (with stride of {}) to {}, expected \
+                         {}, got {}",
+                        ptr, stride, m, expected, got
+                    );
+                    x = true;
+                }
+            }
+        }
+    }
+    assert!(!x);
+}
+
+#[test]
+fn align_offset_euclid_matches_v4() {
+    for apow in 0..usize::BITS {
+        let align = 1usize << apow;
+        for ptr in crate::test_ptrs(align, 256) {
+            for stride in crate::test_strides() {
+                assert_eq!(
+                    align_offset_euclid(ptr, stride, align),
+                    unsafe { crate::align_offset_v4(ptr, stride, align) },
+                    "align_offset_euclid: aligning {:x} (with stride of {}) to {}",
+                    ptr,
+                    stride,
+                    align
+                );
+            }
+        }
+    }
+    // Large moduli, where the final product overflows a `usize`.
+    // The largest prime below `2^usize::BITS`.
+    #[cfg(target_pointer_width = "16")]
+    let m = usize::MAX - 14;
+    #[cfg(target_pointer_width = "32")]
+    let m = usize::MAX - 4;
+    #[cfg(target_pointer_width = "64")]
+    let m = usize::MAX - 58;
+    let o = align_offset_euclid(1, usize::MAX / 3, m);
+    assert_eq!(
+        (1 + (usize::MAX / 3) as u128 * o as u128) % m as u128,
+        0,
+        "{}",
+        o
+    );
+}
@@ -0,0 +1,140 @@
+//! Variants for an alignment known at compile time, as a const generic parameter.
+//!
+//! Everything that only depends on the alignment, including the number of Newton iterations
+//! needed for the modular inverse, is computed at compile time, which also lets us assert that
+//! the alignment is a power of two, making these variants safe.
+
+/// The alignment `ALIGN`, and everything derived from it.
+struct Align<const ALIGN: usize>;
+
+impl<const ALIGN: usize> Align<ALIGN> {
+    /// Fails the build if `ALIGN` is not a power of two.
+    const ASSERT_POW_2: () = assert!(ALIGN.is_power_of_two(), "ALIGN must be a power of two");
+    /// `ALIGN - 1`.
+    const MASK: usize = ALIGN.wrapping_sub(1);
+    /// `s` such that `ALIGN == 2^s`.
+    const POW: u32 = ALIGN.trailing_zeros();
+    /// The number of Newton iterations needed to get from an inverse modulo 2⁴ = 16 to an inverse
+    /// modulo `ALIGN`, or any smaller power of two.
+    const INV_ITERATIONS: u32 = {
+        let mut iterations = 0;
+        let mut going_modpow = 4;
+        while going_modpow < Self::POW {
+            iterations += 1;
+            going_modpow <<= 1;
+        }
+        iterations
+    };
+
+    /// Calculate multiplicative modular inverse of `x` modulo `ALIGN`, and hence modulo any
+    /// smaller power of two.
+    ///
+    /// This implementation is tailored for align_offset and has following preconditions:
+    ///
+    /// * `x` is odd.
+    ///
+    /// It also leaves reducing the result modulu the requested modulus to the caller.
+    ///
+    /// Implementation of this function shall not panic. Ever.
+    #[inline]
+    fn mod_inv(x: usize) -> usize {
+        /// Multiplicative modular inverse table modulo 2⁴ = 16.
+        ///
+        /// Note, that this table does not contain values where inverse does not exist (i.e., for
+        /// `0⁻¹ mod 16`, `2⁻¹ mod 16`, etc.)
+        const INV_TABLE_MOD_16: [u8; 8] = [1, 11, 13, 7, 9, 3, 5, 15];
+
+        let mut inverse = INV_TABLE_MOD_16[(x & 15) >> 1] as usize;
+        // We iterate "up" using the following formula:
+        //
+        // $$ xy ≡ 1 (mod 2ⁿ) → xy (2 - xy) ≡ 1 (mod 2²ⁿ) $$
+        //
+        // a number of times fixed at compile time, so that this loop is fully unrolled.
+        for _ in 0..Self::INV_ITERATIONS {
+            inverse = inverse.wrapping_mul(2usize.wrapping_sub(x.wrapping_mul(inverse)));
+        }
+        inverse
+    }
+}
+
+/// Calculate the offset, in elements of size `stride`, that needs to be applied to the address `p`
+/// for it to become aligned to `ALIGN`, or `usize::MAX` if no such offset exists.
+///
+/// `ALIGN` must be a power of two, otherwise this fails to build:
+///
+/// ```compile_fail
+/// bench_align_offset::align_offset_to::<24>(8, 24);
+/// ```
+#[inline]
+pub fn align_offset_to<const ALIGN: usize>(p: usize, stride: usize) -> usize {
+    let () = Align::<ALIGN>::ASSERT_POW_2;
+
+    let a_minus_one = Align::<ALIGN>::MASK;
+    let pmoda = p & a_minus_one;
+
+    if pmoda == 0 {
+        // Already aligned. Yay!
+        return 0;
+    }
+
+    if stride <= 1 {
+        return if stride == 0 {
+            // If the pointer is not aligned, and the element is zero-sized, then no amount of
+            // elements will ever align the pointer.
+            !0
+        } else {
+            ALIGN.wrapping_sub(pmoda)
+        };
+    }
+
+    let smoda = stride & a_minus_one;
+    // stride = 0 is handled above.
+    let gcdpow = stride.trailing_zeros().min(Align::<ALIGN>::POW);
+    let gcd = 1usize << gcdpow;
+
+    if p & (gcd.wrapping_sub(1)) == 0 {
+        // See `align_offset_v4` for the derivation.
+        let a2 = ALIGN >> gcdpow;
+        let a2minus1 = a2.wrapping_sub(1);
+        let s2 = smoda >> gcdpow;
+        let minusp2 = a2.wrapping_sub(pmoda >> gcdpow);
+        // mod_inv returns a result which may be out of $a'$-s range, but it's fine to
+        // multiply modulo `2^usize::BITS` here, and then take modulo $a'$ afterwards.
+        return (minusp2.wrapping_mul(Align::<ALIGN>::mod_inv(s2))) & a2minus1;
+    }
+
+    // Cannot be aligned at all.
+    usize::MAX
+}
+
+#[test]
+fn align_offset_to_matches_v4() {
+    fn check<const ALIGN: usize>() -> bool {
+        let mut x = false;
+        for ptr in crate::test_ptrs(ALIGN, 2048) {
+            for stride in crate::test_strides() {
+                let expected = unsafe { crate::align_offset_v4(ptr, stride, ALIGN) };
+                let got = align_offset_to::<ALIGN>(ptr, stride);
+                if got != expected {
+                    eprintln!(
+                        "align_offset_to: aligning {:x} (with stride of {}) to {}, expected {}, \
+                         got {}",
+                        ptr, stride, ALIGN, expected, got
+                    );
+                    x = true;
+                }
+            }
+        }
+        x
+    }
+
+    macro_rules! check {
+        ($($pow:literal),*) => {
+            false $(| check::<{ 1 << $pow }>())*
+        };
+    }
+
+    assert!(!check![
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20
+    ]);
+}
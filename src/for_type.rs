@@ -0,0 +1,135 @@
+//! Variants for a stride known at compile time, as the size of a type.
+//!
+//! Everything that only depends on the stride, most notably the inverse of its odd part, is
+//! precomputed as associated consts, so that with a constant alignment the whole computation
+//! reduces to a shift, a subtraction and a multiplication.
+
+use crate::word::Word;
+use core::marker::PhantomData;
+use core::mem;
+
+/// Multiplicative modular inverse of the odd `x` modulo `2^usize::BITS`.
+///
+/// This is [`mod_pow_2_inv_newton`](crate::mod_inv::mod_pow_2_inv_newton)`(x, usize::BITS)`, which
+/// cannot be called here: it is generic over [`Word`], whose methods are not `const`, and this is
+/// needed to initialize `Stride::ODD_INV`.
+const fn mod_word_inv(x: usize) -> usize {
+    // `x` is its own inverse modulo 2³ = 8; we iterate "up" using the following formula:
+    //
+    // $$ xy ≡ 1 (mod 2ⁿ) → xy (2 - xy) ≡ 1 (mod 2²ⁿ) $$
+    let mut inverse = x;
+    let mut going_modpow = 3;
+    while going_modpow < usize::BITS {
+        inverse = inverse.wrapping_mul(2usize.wrapping_sub(x.wrapping_mul(inverse)));
+        going_modpow <<= 1;
+    }
+    inverse
+}
+
+/// The stride of `T`, and everything derived from it.
+struct Stride<T>(PhantomData<T>);
+
+impl<T> Stride<T> {
+    /// The stride itself.
+    const SIZE: usize = mem::size_of::<T>();
+    /// `s` such that `2^s` is the largest power of two dividing the stride, or `usize::BITS` for
+    /// zero-sized types.
+    const POW: u32 = Self::SIZE.trailing_zeros();
+    /// The stride divided by its largest power-of-two factor.
+    const ODD: usize = if Self::SIZE == 0 {
+        0
+    } else {
+        Self::SIZE >> Self::POW
+    };
+    /// The inverse of `ODD` modulo `2^usize::BITS`, and hence modulo any smaller power of two.
+    const ODD_INV: usize = mod_word_inv(Self::ODD);
+}
+
+/// Calculate the offset, in elements of `T`, that needs to be applied to the address `p` for it to
+/// become aligned to `a`, or `usize::MAX` if no such offset exists.
+///
+/// # Safety
+///
+/// `a` must be a power of two.
+#[inline]
+pub unsafe fn align_offset_for<T>(p: usize, a: usize) -> usize {
+    let a_minus_one = a.wrapping_sub(1);
+    let pmoda = p & a_minus_one;
+
+    if Stride::<T>::SIZE == 0 {
+        // If the pointer is not aligned, and the element is zero-sized, then no amount of
+        // elements will ever align the pointer.
+        return if pmoda == 0 { 0 } else { !0 };
+    }
+
+    // a is power-of-two so cannot be 0.
+    let gcdpow = Stride::<T>::POW.min(a.cttz_nonzero());
+    let gcd = 1usize << gcdpow;
+
+    if p & (gcd.wrapping_sub(1)) == 0 {
+        // As in `align_offset_v4`, with $g = gcd(a, s)$, $a' = a/g$, $s' = s/g$, $p' = p/g$:
+        //
+        // $$ o = (a' - (p' mod a')) * ((s')⁻¹ mod a') $$
+        //
+        // If $g$ is the largest power of two dividing $s$, then $s'$ is `ODD`; otherwise $g = a$,
+        // so $a' = 1$ and the result is 0 regardless of the inverse. Either way, `ODD_INV` is
+        // fine to use, as it is the inverse of `ODD` modulo any power of two.
+        let a2minus1 = a_minus_one >> gcdpow;
+        let minusp2 = (a >> gcdpow).wrapping_sub(pmoda >> gcdpow);
+        return minusp2.wrapping_mul(Stride::<T>::ODD_INV) & a2minus1;
+    }
+
+    // Cannot be aligned at all.
+    usize::MAX
+}
+
+#[test]
+fn mod_word_inv_matches_mod_inv() {
+    for x in (1..1 << 16)
+        .step_by(2)
+        .chain([usize::MAX, usize::MAX - 2, 0x9e37_79b9])
+    {
+        assert_eq!(
+            mod_word_inv(x),
+            crate::mod_inv::mod_pow_2_inv_newton(x, usize::BITS),
+            "{}⁻¹",
+            x
+        );
+    }
+}
+
+#[test]
+fn align_offset_for_matches_v4() {
+    fn check<T>() -> bool {
+        let stride = mem::size_of::<T>();
+        let mut x = false;
+        let mut align = 1;
+        while align <= 1024 {
+            for ptr in 0..4 * align {
+                let expected = unsafe { crate::align_offset_v4(ptr, stride, align) };
+                let got = unsafe { align_offset_for::<T>(ptr, align) };
+                if got != expected {
+                    eprintln!(
+                        "align_offset_for: aligning {:x} (with stride of {}) to {}, expected {}, \
+                         got {}",
+                        ptr, stride, align, expected, got
+                    );
+                    x = true;
+                }
+            }
+            align <<= 1;
+        }
+        x
+    }
+
+    macro_rules! check {
+        ($($n:literal),*) => {
+            false $(|| check::<[u8; $n]>())*
+        };
+    }
+
+    assert!(!check![
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 16, 20, 24, 48, 96, 1024, 1536
+    ]);
+    assert!(!check::<u64>() && !check::<(u32, u16)>() && !check::<[u128; 3]>());
+}
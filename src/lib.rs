@@ -1,48 +1,99 @@
-#![feature(core_intrinsics)]
+#![cfg_attr(feature = "nightly", feature(core_intrinsics))]
+#![cfg_attr(feature = "nightly", allow(internal_features))]
 
+pub mod addr;
+pub mod aligner;
+pub mod batch;
+pub mod checked;
+pub mod congruence;
+pub mod euclid;
+pub mod for_align;
+pub mod for_type;
+pub mod mod_inv;
+pub mod ptr;
+pub mod registry;
+pub mod simd;
+pub mod slice;
 mod v0;
 mod v1;
 mod v2;
 mod v3;
 mod v4;
+mod v5;
+mod v6;
+pub mod word;
 
 pub use v0::align_offset as align_offset_v0;
+pub use v0::align_offset_const as align_offset_v0_const;
 pub use v1::align_offset as align_offset_v1;
+pub use v1::align_offset_const as align_offset_v1_const;
 pub use v2::align_offset as align_offset_v2;
+pub use v2::align_offset_const as align_offset_v2_const;
 pub use v3::align_offset as align_offset_v3;
+pub use v3::align_offset_const as align_offset_v3_const;
 pub use v4::align_offset as align_offset_v4;
+pub use v4::align_offset_const as align_offset_v4_const;
+pub use v5::align_offset as align_offset_v5;
+pub use v6::align_offset as align_offset_v6;
 
-pub const ALIGN_OFFSET_FNS: [unsafe fn(usize, usize, usize) -> usize; 5] = [
-    align_offset_v0,
-    align_offset_v1,
-    align_offset_v2,
-    align_offset_v3,
-    align_offset_v4,
-];
+pub use aligner::{aligned_indices, AlignedIndices, Aligner};
+pub use batch::align_offsets;
+pub use checked::{align_offset, AlignError};
+pub use euclid::align_offset_euclid;
+pub use for_align::align_offset_to;
+pub use for_type::align_offset_for;
+pub use ptr::{AlignOffsetExt, AlignOffsetVariant};
+pub use registry::{Variant, VARIANTS};
+pub use simd::{align_offsets_simd, Isa};
+pub use word::Word;
+
+/// Naive but definitely correct way to find the *first* aligned element of stride::<T>.
+#[cfg(test)]
+fn naive_align_offset(ptr: usize, stride: usize, align: usize) -> usize {
+    for el in 0..align {
+        if (ptr + el * stride).is_multiple_of(align) {
+            return el;
+        }
+    }
+    usize::MAX
+}
+
+/// Strides for the tests checking a specialized implementation against v4: all the small ones,
+/// then a few large ones, up to `usize::MAX`.
+#[cfg(test)]
+fn test_strides() -> impl Iterator<Item = usize> {
+    (0..=64).chain(
+        [96, 1000, 4096, 24 << 10, 3 << 20, usize::MAX]
+            .iter()
+            .copied(),
+    )
+}
+
+/// Pointers for the tests checking a specialized implementation against v4: `n` around a
+/// multiple of `align`, and `n` around the top of the address space.
+#[cfg(test)]
+fn test_ptrs(align: usize, n: usize) -> impl Iterator<Item = usize> {
+    (0..n)
+        .map(move |i| align.wrapping_mul(3).wrapping_add(i).wrapping_sub(n / 2))
+        .chain((0..n).map(|i| usize::MAX - i))
+}
 
 #[test]
 fn align_offset_weird_strides() {
     unsafe fn test_weird_stride(ptr: usize, stride: usize, align: usize) -> bool {
-        let mut expected = usize::max_value();
-        // Naive but definitely correct way to find the *first* aligned element of stride::<T>.
-        for el in 0..align {
-            if (ptr + el * stride) % align == 0 {
-                expected = el;
-                break;
-            }
-        }
+        let expected = naive_align_offset(ptr, stride, align);
         let mut ret = false;
-        for i in 0..ALIGN_OFFSET_FNS.len() {
-            let got = ALIGN_OFFSET_FNS[i](ptr, stride, align);
+        for variant in VARIANTS {
+            let got = (variant.func)(ptr, stride, align);
             if got != expected {
                 eprintln!(
-                    "align_offset_v{}: aligning {:x} (with stride of {}) to {}, expected {}, got {}",
-                    i, ptr, stride, align, expected, got
+                    "{}: aligning {:x} (with stride of {}) to {}, expected {}, got {}",
+                    variant.name, ptr, stride, align, expected, got
                 );
                 ret |= true;
             }
         }
-        return ret;
+        ret
     }
 
     // For pointers of stride != 1, we verify the algorithm against the naivest possible
@@ -61,3 +112,157 @@ fn align_offset_weird_strides() {
     }
     assert!(!x);
 }
+
+#[test]
+fn align_offset_differential() {
+    /// `<*const T>::align_offset` for `T` of size `N`.
+    fn std_align_offset<const N: usize>(ptr: usize, align: usize) -> usize {
+        (ptr as *const [u8; N]).align_offset(align)
+    }
+
+    macro_rules! std_align_offsets {
+        ($($n:literal),*) => {
+            [$(($n, std_align_offset::<$n> as fn(usize, usize) -> usize)),*]
+        };
+    }
+
+    let std_align_offsets = std_align_offsets![
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 16, 20, 24, 32, 40, 48, 64, 96, 128, 256, 1024
+    ];
+
+    // The first divergence of each variant, if any, as `(ptr, stride, align, expected, got)`.
+    let mut first_divergence = vec![None; VARIANTS.len()];
+    let mut align = 1;
+    while align <= 1024 {
+        for ptr in 0usize..4 * align {
+            for &(stride, std_align_offset) in std_align_offsets.iter() {
+                let expected = naive_align_offset(ptr, stride, align);
+                assert_eq!(
+                    std_align_offset(ptr, align),
+                    expected,
+                    "std: aligning {:x} (with stride of {}) to {}",
+                    ptr,
+                    stride,
+                    align
+                );
+                for (variant, divergence) in VARIANTS.iter().zip(first_divergence.iter_mut()) {
+                    let got = unsafe { (variant.func)(ptr, stride, align) };
+                    if got != expected && divergence.is_none() {
+                        *divergence = Some((ptr, stride, align, expected, got));
+                    }
+                }
+            }
+        }
+        align <<= 1;
+    }
+
+    let mut x = false;
+    for (variant, divergence) in VARIANTS.iter().zip(first_divergence) {
+        if let Some((ptr, stride, align, expected, got)) = divergence {
+            eprintln!(
+                "{}: aligning {:x} (with stride of {}) to {}, expected {}, got {}",
+                variant.name, ptr, stride, align, expected, got
+            );
+            x = true;
+        }
+    }
+    assert!(!x);
+}
+
+#[test]
+fn align_offset_const_matches_runtime() {
+    /// Number of small inputs: `p < 64`, `stride < 32`, `align <= 1 << 9`.
+    const SMALL: usize = 64 * 32 * 10;
+    /// Pointers and strides combined with every alignment from `1 << 10` up, for which the
+    /// `mod_inv` loops iterate more than once.
+    const LARGE_PTRS: [usize; 4] = [8, 0x1000, (usize::MAX / 3) & !7, usize::MAX & !7];
+    const LARGE_STRIDES: [usize; 4] = [1, 3, 24, 40];
+    /// Number of large alignments.
+    const LARGE_POWS: usize = usize::BITS as usize - 10;
+    /// Number of `(p, stride, align)` inputs.
+    const N: usize = SMALL + LARGE_PTRS.len() * LARGE_STRIDES.len() * LARGE_POWS;
+
+    /// The `i`-th input.
+    const fn input(i: usize) -> (usize, usize, usize) {
+        if i < SMALL {
+            (i % 64, (i / 64) % 32, 1 << (i / (64 * 32)))
+        } else {
+            let j = i - SMALL;
+            (
+                LARGE_PTRS[j / (LARGE_POWS * LARGE_STRIDES.len())],
+                LARGE_STRIDES[(j / LARGE_POWS) % LARGE_STRIDES.len()],
+                1 << (10 + j % LARGE_POWS),
+            )
+        }
+    }
+
+    /// Evaluate a `const` variant on all the inputs with alignments up to `max_align`, at compile
+    /// time (as `static` initializers are).
+    macro_rules! const_results {
+        ($func:path, $max_align:expr) => {{
+            let mut results = [None; N];
+            let mut i = 0;
+            while i < N {
+                let (p, stride, align) = input(i);
+                if align <= $max_align {
+                    results[i] = Some(unsafe { $func(p, stride, align) });
+                }
+                i += 1;
+            }
+            results
+        }};
+    }
+
+    /// `2^(usize::BITS / 2)`, the limit of v1 and v2, and twice that of v0.
+    const HALF_BITS_POW_2: usize = 1 << (usize::BITS / 2);
+
+    static CONST_RESULTS: [(&str, [Option<usize>; N]); 5] = [
+        (
+            "align_offset_v0",
+            const_results!(align_offset_v0_const, HALF_BITS_POW_2 >> 1),
+        ),
+        (
+            "align_offset_v1",
+            const_results!(align_offset_v1_const, HALF_BITS_POW_2),
+        ),
+        (
+            "align_offset_v2",
+            const_results!(align_offset_v2_const, HALF_BITS_POW_2),
+        ),
+        (
+            "align_offset_v3",
+            const_results!(align_offset_v3_const, usize::MAX),
+        ),
+        (
+            "align_offset_v4",
+            const_results!(align_offset_v4_const, usize::MAX),
+        ),
+    ];
+
+    for variant in VARIANTS.iter().filter(|variant| variant.is_const) {
+        let (_, results) = CONST_RESULTS
+            .iter()
+            .find(|(name, _)| *name == variant.name)
+            .unwrap_or_else(|| panic!("{} has no const results", variant.name));
+        for (i, &expected) in results.iter().enumerate() {
+            let (p, stride, align) = input(i);
+            assert_eq!(
+                expected.is_some(),
+                align <= variant.max_align(),
+                "{}: const results do not follow the registry limit at {}",
+                variant.name,
+                align
+            );
+            let expected = match expected {
+                Some(expected) => expected,
+                None => continue,
+            };
+            let got = unsafe { (variant.func)(p, stride, align) };
+            assert_eq!(
+                got, expected,
+                "{}: aligning {:x} (with stride of {}) to {}",
+                variant.name, p, stride, align
+            );
+        }
+    }
+}
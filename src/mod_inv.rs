@@ -0,0 +1,163 @@
+//! Multiplicative inverses modulo powers of two.
+//!
+//! All strategies find a `y` such that $xy ≡ 1 (mod 2^s)$ for some small $s$, and then iterate
+//! "up" using the following formula:
+//!
+//! $$ xy ≡ 1 (mod 2ⁿ) → xy (2 - xy) ≡ 1 (mod 2²ⁿ) $$
+//!
+//! until 2²ⁿ ≥ m. They differ in how the initial `y` is found, trading a memory load against
+//! Newton iterations.
+//!
+//! All of them have the following preconditions:
+//!
+//! * `x` is odd, as otherwise there is no inverse (and the result is meaningless);
+//! * `mpow <= W::BITS`, where the modulus is `m = 2^mpow`.
+//!
+//! and return a result already reduced modulo `m`.
+
+use crate::word::Word;
+
+/// Multiplicative modular inverse table modulo 2⁴ = 16.
+///
+/// Note, that this table does not contain values where inverse does not exist (i.e., for
+/// `0⁻¹ mod 16`, `2⁻¹ mod 16`, etc.)
+const INV_TABLE_MOD_16: [u8; 8] = [1, 11, 13, 7, 9, 3, 5, 15];
+
+/// Multiplicative modular inverse table modulo 2⁸ = 256, computed with Newton iterations from `x`
+/// itself.
+///
+/// Like `INV_TABLE_MOD_16`, this table does not contain values where inverse does not exist.
+const INV_TABLE_MOD_256: [u8; 128] = {
+    let mut table = [0u8; 128];
+    let mut i = 0;
+    while i < 128 {
+        let x = (2 * i + 1) as u8;
+        // Valid mod 2³, then 2⁶, then 2¹².
+        let mut inverse = x;
+        inverse = inverse.wrapping_mul(2u8.wrapping_sub(x.wrapping_mul(inverse)));
+        inverse = inverse.wrapping_mul(2u8.wrapping_sub(x.wrapping_mul(inverse)));
+        table[i] = inverse;
+        i += 1;
+    }
+    table
+};
+
+/// Run Newton iterations on `inverse`, valid mod `2^inverse_pow`, until it is valid mod
+/// `2^mpow`, and reduce it mod `2^mpow`.
+///
+/// Implementation of this function shall not panic. Ever.
+#[inline]
+fn iterate_up<W: Word>(x: W, mut inverse: W, inverse_pow: u32, mpow: u32) -> W {
+    // Larger `mpow`s are out of contract, but must not make `going_modpow` overflow below.
+    let mpow = mpow.min(W::BITS);
+    let mut going_modpow = inverse_pow;
+    while going_modpow < mpow {
+        // y = y * (2 - xy)
+        //
+        // Note, that we use wrapping operations here intentionally – the original formula
+        // uses e.g., subtraction `mod n`. It is entirely fine to do them `mod 2^W::BITS`
+        // instead, as wrapping operations on `W` do, because we take the result `mod n` at the
+        // end anyway.
+        inverse = inverse.wrapping_mul(W::from_u8(2).wrapping_sub(x.wrapping_mul(inverse)));
+        going_modpow <<= 1;
+    }
+    inverse & mask(mpow)
+}
+
+/// `2^mpow - 1`, saturating at `W::MAX`.
+///
+/// Implementation of this function shall not panic. Ever.
+#[inline]
+fn mask<W: Word>(mpow: u32) -> W {
+    if mpow == 0 {
+        W::ZERO
+    } else {
+        W::MAX >> W::BITS.saturating_sub(mpow)
+    }
+}
+
+/// Calculate multiplicative modular inverse of `x` modulo `2^mpow`, starting from a lookup in a
+/// 16-entry table, as the `align_offset` variants do.
+///
+/// Implementation of this function shall not panic. Ever.
+#[inline]
+pub fn mod_pow_2_inv_table_16<W: Word>(x: W, mpow: u32) -> W {
+    let table_inverse = W::from_u8(INV_TABLE_MOD_16[(x.low_u8() & 15) as usize >> 1]);
+    iterate_up(x, table_inverse, 4, mpow)
+}
+
+/// Calculate multiplicative modular inverse of `x` modulo `2^mpow`, starting from `x` itself,
+/// which is its own inverse modulo 2³ = 8, avoiding any memory loads.
+///
+/// Implementation of this function shall not panic. Ever.
+#[inline]
+pub fn mod_pow_2_inv_newton<W: Word>(x: W, mpow: u32) -> W {
+    iterate_up(x, x, 3, mpow)
+}
+
+/// Calculate multiplicative modular inverse of `x` modulo `2^mpow`, starting from a lookup in a
+/// 128-entry table, saving a Newton iteration over [`mod_pow_2_inv_table_16`].
+///
+/// Implementation of this function shall not panic. Ever.
+#[inline]
+pub fn mod_pow_2_inv_table_256<W: Word>(x: W, mpow: u32) -> W {
+    let table_inverse = W::from_u8(INV_TABLE_MOD_256[x.low_u8() as usize >> 1]);
+    iterate_up(x, table_inverse, 8, mpow)
+}
+
+#[test]
+fn mod_pow_2_inv_exhaustive_u16() {
+    for x in (1..=u16::MAX).step_by(2) {
+        for mpow in 0..=u16::BITS {
+            let m_minus_one: u16 = mask(mpow);
+            for inverse in [
+                mod_pow_2_inv_table_16(x, mpow),
+                mod_pow_2_inv_newton(x, mpow),
+                mod_pow_2_inv_table_256(x, mpow),
+            ] {
+                assert_eq!(inverse & !m_minus_one, 0, "{}⁻¹ mod 2^{}", x, mpow);
+                assert_eq!(
+                    x.wrapping_mul(inverse) & m_minus_one,
+                    1 & m_minus_one,
+                    "{}⁻¹ mod 2^{}",
+                    x,
+                    mpow
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn mod_pow_2_inv_wide() {
+    macro_rules! check {
+        ($w:ty) => {
+            let mut x: $w = 1;
+            for _ in 0..1024 {
+                // An arbitrary sequence of odd numbers, covering all bits.
+                x = x.wrapping_mul(0x9e37_79b9_7f4a_7c15_f39c_c060_5ced_c835u128 as $w) | 1;
+                for mpow in 0..=<$w>::BITS {
+                    let m_minus_one: $w = mask(mpow);
+                    for inverse in [
+                        mod_pow_2_inv_table_16(x, mpow),
+                        mod_pow_2_inv_newton(x, mpow),
+                        mod_pow_2_inv_table_256(x, mpow),
+                    ] {
+                        assert_eq!(inverse & !m_minus_one, 0, "{}⁻¹ mod 2^{}", x, mpow);
+                        assert_eq!(
+                            x.wrapping_mul(inverse) & m_minus_one,
+                            1 & m_minus_one,
+                            "{}⁻¹ mod 2^{}",
+                            x,
+                            mpow
+                        );
+                    }
+                }
+            }
+        };
+    }
+    check!(u32);
+    check!(u64);
+    check!(u128);
+    check!(usize);
+}
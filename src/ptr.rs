@@ -0,0 +1,199 @@
+//! `align_offset` on typed pointers, with the variant chosen by a type parameter.
+
+use crate::checked::AlignError;
+use crate::registry::{Variant, VARIANTS};
+use core::mem;
+use core::ptr::NonNull;
+
+/// A type standing for one of the variants in [`VARIANTS`].
+pub trait AlignOffsetVariant {
+    /// The descriptor of the variant.
+    const VARIANT: &'static Variant;
+
+    /// The variant itself, called directly rather than through [`Variant::func`], so that it can
+    /// be inlined.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Variant::func`].
+    unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize;
+}
+
+/// The variant in [`VARIANTS`] with the given name.
+const fn variant_named(name: &str) -> &'static Variant {
+    let mut i = 0;
+    while i < VARIANTS.len() {
+        let (a, b) = (VARIANTS[i].name.as_bytes(), name.as_bytes());
+        if a.len() == b.len() {
+            let mut j = 0;
+            while j < a.len() && a[j] == b[j] {
+                j += 1;
+            }
+            if j == a.len() {
+                return &VARIANTS[i];
+            }
+        }
+        i += 1;
+    }
+    panic!("no variant with this name in VARIANTS");
+}
+
+/// The single list of variant markers, with the module of each variant, which the marker types
+/// and [`for_each_variant_marker!`] are both generated from.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __variant_markers {
+    ($mode:tt $args:tt) => {
+        $crate::__variant_markers! {
+            $mode $args
+            [V0 v0] [V1 v1] [V2 v2] [V3 v3] [V4 v4] [V5 v5] [V6 v6]
+        }
+    };
+    (declare () $([$marker:ident $module:ident])*) => {
+        $(
+            #[doc = concat!("Marker type for `align_offset_", stringify!($module), "`.")]
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+            pub struct $marker;
+
+            impl AlignOffsetVariant for $marker {
+                const VARIANT: &'static Variant =
+                    variant_named(concat!("align_offset_", stringify!($module)));
+
+                #[inline(always)]
+                unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
+                    $crate::$module::align_offset(p, stride, a)
+                }
+            }
+        )*
+    };
+    (for_each ($V:ident, $body:expr) $([$marker:ident $module:ident])*) => {
+        $({
+            type $V = $crate::ptr::$marker;
+            $body;
+        })*
+    };
+}
+
+__variant_markers!(declare());
+
+/// Evaluate an expression once for every variant marker type, in the order of [`VARIANTS`], with
+/// the given name standing for the marker.
+///
+/// ```
+/// use bench_align_offset::ptr::AlignOffsetVariant;
+///
+/// let mut names = Vec::new();
+/// bench_align_offset::for_each_variant_marker!(V => names.push(V::VARIANT.name));
+/// assert_eq!(names[0], "align_offset_v0");
+/// ```
+#[macro_export]
+macro_rules! for_each_variant_marker {
+    ($V:ident => $body:expr) => {
+        $crate::__variant_markers!(for_each($V, $body))
+    };
+}
+
+/// `align_offset` for pointers to `T`, with a stride of `size_of::<T>()`.
+pub trait AlignOffsetExt {
+    /// Calculate the offset, in elements of `T`, that needs to be applied to the pointer for it to
+    /// become aligned to `align`, or `usize::MAX` if no such offset exists, using the variant `V`.
+    ///
+    /// Like `<*const T>::align_offset`, a pointer to a zero-sized `T` has an offset of 0 if it is
+    /// already aligned, and `usize::MAX` otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two, or is larger than `V` supports.
+    fn align_offset_with<V: AlignOffsetVariant>(self, align: usize) -> usize;
+}
+
+/// The address `p` of a pointer to `T`, aligned to `align` using the variant `V`.
+#[inline]
+fn align_offset_with<T, V: AlignOffsetVariant>(p: usize, align: usize) -> usize {
+    // The same checks as `checked::align_offset`, but on the inlinable variant. With a constant
+    // `align`, as for `align_of::<U>()`, they fold away.
+    if !align.is_power_of_two() {
+        panic!("{}: {}", V::VARIANT.name, AlignError::NotPowerOfTwo(align));
+    }
+    if align > V::VARIANT.max_align() {
+        panic!(
+            "{}: {}",
+            V::VARIANT.name,
+            AlignError::UnsupportedAlignment(align)
+        );
+    }
+    // SAFETY: `align` was checked to be a power of two above. A zero-sized `T` gives a zero
+    // stride, for which every variant returns 0 or `usize::MAX`, as `<*const T>::align_offset`.
+    unsafe { V::align_offset(p, mem::size_of::<T>(), align) }
+}
+
+impl<T> AlignOffsetExt for *const T {
+    #[inline]
+    fn align_offset_with<V: AlignOffsetVariant>(self, align: usize) -> usize {
+        align_offset_with::<T, V>(self as usize, align)
+    }
+}
+
+impl<T> AlignOffsetExt for *mut T {
+    #[inline]
+    fn align_offset_with<V: AlignOffsetVariant>(self, align: usize) -> usize {
+        align_offset_with::<T, V>(self as usize, align)
+    }
+}
+
+impl<T> AlignOffsetExt for NonNull<T> {
+    #[inline]
+    fn align_offset_with<V: AlignOffsetVariant>(self, align: usize) -> usize {
+        self.as_ptr().align_offset_with::<V>(align)
+    }
+}
+
+#[test]
+fn variant_markers_match_registry() {
+    let mut names = Vec::new();
+    crate::for_each_variant_marker!(V => names.push(V::VARIANT.name));
+    let expected: Vec<_> = VARIANTS.iter().map(|variant| variant.name).collect();
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn align_offset_with_matches_std() {
+    fn check<T, V: AlignOffsetVariant>() {
+        let mut align = 1;
+        while align <= 1024 {
+            for p in 1usize..4 * align {
+                let ptr = p as *const T;
+                let expected = ptr.align_offset(align);
+                assert_eq!(
+                    ptr.align_offset_with::<V>(align),
+                    expected,
+                    "{}: aligning {:x} (with stride of {}) to {}",
+                    V::VARIANT.name,
+                    p,
+                    mem::size_of::<T>(),
+                    align
+                );
+                assert_eq!((ptr as *mut T).align_offset_with::<V>(align), expected);
+                let non_null = NonNull::new(ptr as *mut T).unwrap();
+                assert_eq!(non_null.align_offset_with::<V>(align), expected);
+            }
+            align <<= 1;
+        }
+    }
+
+    macro_rules! check_all {
+        ($($t:ty),*) => {
+            $(
+                crate::for_each_variant_marker!(V => check::<$t, V>());
+            )*
+        };
+    }
+
+    check_all!((), u8, u16, u32, u64, [u8; 3], [u8; 24], [u32; 5], [u64; 3]);
+}
+
+#[test]
+#[should_panic(expected = "alignment 24 is not a power of two")]
+fn align_offset_with_not_power_of_two() {
+    (16 as *const u64).align_offset_with::<V4>(24);
+}
@@ -0,0 +1,183 @@
+//! Descriptors of all the `align_offset` variants in this crate.
+//!
+//! Tests, benchmarks and any other tooling should iterate over [`VARIANTS`] rather than naming the
+//! variants one by one, so that adding a variant only means adding an entry here.
+
+use crate::word::Word;
+
+/// The strategy a variant uses to compute the multiplicative inverse of the stride modulo the
+/// alignment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ModInverse {
+    /// Look up the inverse modulo 16 in `INV_TABLE_MOD_16`, then run Newton iterations, tracking
+    /// the modulus reached so far (`going_mod`) and comparing it against the requested modulus.
+    TableNewtonByModulus,
+    /// Like [`ModInverse::TableNewtonByModulus`], but tracking the exponent of the modulus
+    /// reached so far (`going_modpow`), so the iteration count is decided with shifts only.
+    TableNewtonByPower,
+    /// Like [`ModInverse::TableNewtonByPower`], but leaving the reduction of the result modulo
+    /// the requested modulus to the caller.
+    TableNewtonByPowerUnreduced,
+    /// Start from `3x ⊕ 2`, the inverse modulo 32, without any table, and run a fixed number of
+    /// Newton iterations up to the full width of the word, leaving the reduction to the caller.
+    SeedNewtonFullWidth,
+}
+
+/// The range of alignments a variant supports, in terms of the width of the word it runs on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AlignLimit {
+    /// All powers of two are supported.
+    Unlimited,
+    /// Alignments up to 16, or smaller than `2^(W::BITS / 2)`, are supported.
+    BelowHalfBits,
+    /// Alignments up to 16, or no larger than `2^(W::BITS / 2)`, are supported.
+    UpToHalfBits,
+}
+
+impl AlignLimit {
+    /// The largest alignment supported for words of type `W`.
+    pub fn max_align<W: Word>(self) -> W {
+        let pow = match self {
+            AlignLimit::Unlimited => W::BITS - 1,
+            AlignLimit::BelowHalfBits => (W::BITS / 2 - 1).max(4),
+            AlignLimit::UpToHalfBits => (W::BITS / 2).max(4),
+        };
+        W::ONE << pow
+    }
+}
+
+/// A variant, for every word width it can run on.
+#[derive(Clone, Copy, Debug)]
+pub struct WordFuncs {
+    pub u8: unsafe fn(u8, u8, u8) -> u8,
+    pub u16: unsafe fn(u16, u16, u16) -> u16,
+    pub u32: unsafe fn(u32, u32, u32) -> u32,
+    pub u64: unsafe fn(u64, u64, u64) -> u64,
+    pub u128: unsafe fn(u128, u128, u128) -> u128,
+}
+
+/// Build the [`WordFuncs`] of a generic variant.
+macro_rules! word_funcs {
+    ($func:path) => {
+        WordFuncs {
+            u8: $func,
+            u16: $func,
+            u32: $func,
+            u64: $func,
+            u128: $func,
+        }
+    };
+}
+
+/// Description of a single `align_offset` variant.
+#[derive(Clone, Copy, Debug)]
+pub struct Variant {
+    /// Name of the variant, as exported from the crate root (e.g. `align_offset_v0`).
+    pub name: &'static str,
+    /// Short description of how this variant differs from the others.
+    pub description: &'static str,
+    /// How the variant computes the modular inverse of the stride.
+    pub mod_inverse: ModInverse,
+    /// Preconditions the caller of [`Variant::func`] must uphold.
+    pub preconditions: &'static str,
+    /// The alignments the variant supports. For larger ones it may panic on an arithmetic
+    /// overflow with debug assertions enabled, or never return.
+    pub align_limit: AlignLimit,
+    /// Whether the variant has a `const` twin, exported from the crate root with a `_const`
+    /// suffix (e.g. `align_offset_v0_const`).
+    pub is_const: bool,
+    /// The variant itself, taking `(p, stride, a)`.
+    pub func: unsafe fn(usize, usize, usize) -> usize,
+    /// The variant running on other word widths, emulating targets with other pointer widths.
+    pub word_funcs: WordFuncs,
+}
+
+impl Variant {
+    /// The largest alignment the variant supports for words of type `W`.
+    pub fn max_align<W: Word>(&self) -> W {
+        self.align_limit.max_align()
+    }
+}
+
+/// Preconditions of the variants which support all alignments.
+const POW_2_ALIGN: &str = "`a` is a power of two";
+/// Preconditions of the variants limited to [`AlignLimit::BelowHalfBits`].
+const POW_2_ALIGN_BELOW_HALF_BITS: &str =
+    "`a` is a power of two, up to 16 or smaller than `2^(usize::BITS / 2)`";
+/// Preconditions of the variants limited to [`AlignLimit::UpToHalfBits`].
+const POW_2_ALIGN_UP_TO_HALF_BITS: &str =
+    "`a` is a power of two, up to 16 or no larger than `2^(usize::BITS / 2)`";
+
+/// All the variants, in order of their introduction.
+pub const VARIANTS: &[Variant] = &[
+    Variant {
+        name: "align_offset_v0",
+        description: "The `libcore` implementation, reducing the result with a remainder",
+        mod_inverse: ModInverse::TableNewtonByModulus,
+        preconditions: POW_2_ALIGN_BELOW_HALF_BITS,
+        align_limit: AlignLimit::BelowHalfBits,
+        is_const: true,
+        func: crate::v0::align_offset,
+        word_funcs: word_funcs!(crate::v0::align_offset),
+    },
+    Variant {
+        name: "align_offset_v1",
+        description: "Reduces the result with a mask instead of a remainder",
+        mod_inverse: ModInverse::TableNewtonByModulus,
+        preconditions: POW_2_ALIGN_UP_TO_HALF_BITS,
+        align_limit: AlignLimit::UpToHalfBits,
+        is_const: true,
+        func: crate::v1::align_offset,
+        word_funcs: word_funcs!(crate::v1::align_offset),
+    },
+    Variant {
+        name: "align_offset_v2",
+        description: "Solves modulo `a / gcd`, masking only once at the end of the inverse",
+        mod_inverse: ModInverse::TableNewtonByModulus,
+        preconditions: POW_2_ALIGN_UP_TO_HALF_BITS,
+        align_limit: AlignLimit::UpToHalfBits,
+        is_const: true,
+        func: crate::v2::align_offset,
+        word_funcs: word_funcs!(crate::v2::align_offset),
+    },
+    Variant {
+        name: "align_offset_v3",
+        description: "Counts Newton iterations by the exponent of the modulus",
+        mod_inverse: ModInverse::TableNewtonByPower,
+        preconditions: POW_2_ALIGN,
+        align_limit: AlignLimit::Unlimited,
+        is_const: true,
+        func: crate::v3::align_offset,
+        word_funcs: word_funcs!(crate::v3::align_offset),
+    },
+    Variant {
+        name: "align_offset_v4",
+        description: "Leaves the reduction of the inverse to the final mask",
+        mod_inverse: ModInverse::TableNewtonByPowerUnreduced,
+        preconditions: POW_2_ALIGN,
+        align_limit: AlignLimit::Unlimited,
+        is_const: true,
+        func: crate::v4::align_offset,
+        word_funcs: word_funcs!(crate::v4::align_offset),
+    },
+    Variant {
+        name: "align_offset_v5",
+        description: "Computes the inverse without a table, with a fixed number of iterations",
+        mod_inverse: ModInverse::SeedNewtonFullWidth,
+        preconditions: POW_2_ALIGN,
+        align_limit: AlignLimit::Unlimited,
+        is_const: false,
+        func: crate::v5::align_offset,
+        word_funcs: word_funcs!(crate::v5::align_offset),
+    },
+    Variant {
+        name: "align_offset_v6",
+        description: "Branchless, selecting the unalignable case with a mask",
+        mod_inverse: ModInverse::SeedNewtonFullWidth,
+        preconditions: POW_2_ALIGN,
+        align_limit: AlignLimit::Unlimited,
+        is_const: false,
+        func: crate::v6::align_offset,
+        word_funcs: word_funcs!(crate::v6::align_offset),
+    },
+];
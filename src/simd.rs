@@ -0,0 +1,236 @@
+//! Computing `align_offset` for many pointers sharing the same stride and alignment, several
+//! pointers at a time using SIMD instructions.
+//!
+//! All implementations compute exactly the same results as `align_offset_v4`, using the
+//! precomputed values of an [`Aligner`] broadcast to all lanes.
+
+use crate::aligner::Aligner;
+
+/// An instruction set to compute the offsets with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Isa {
+    /// One pointer at a time, with [`Aligner::offset`].
+    Scalar,
+    /// Two pointers at a time, with SSE2 on x86_64.
+    Sse2,
+    /// Four pointers at a time, with AVX2 on x86_64.
+    Avx2,
+}
+
+impl Isa {
+    /// All instruction sets, available or not.
+    pub const ALL: [Isa; 3] = [Isa::Scalar, Isa::Sse2, Isa::Avx2];
+
+    /// Name of the instruction set, e.g. for benchmark IDs.
+    pub fn name(self) -> &'static str {
+        match self {
+            Isa::Scalar => "scalar",
+            Isa::Sse2 => "sse2",
+            Isa::Avx2 => "avx2",
+        }
+    }
+
+    /// Whether the instruction set is available on the running CPU.
+    pub fn is_available(self) -> bool {
+        match self {
+            Isa::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            Isa::Sse2 => is_x86_feature_detected!("sse2"),
+            #[cfg(target_arch = "x86_64")]
+            Isa::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(not(target_arch = "x86_64"))]
+            Isa::Sse2 | Isa::Avx2 => false,
+        }
+    }
+
+    /// The widest instruction set available on the running CPU.
+    pub fn best() -> Isa {
+        *Isa::ALL
+            .iter()
+            .rev()
+            .find(|isa| isa.is_available())
+            .unwrap_or(&Isa::Scalar)
+    }
+}
+
+/// Like [`align_offsets`](crate::align_offsets), using the widest instruction set available on the
+/// running CPU.
+///
+/// # Panics
+///
+/// Panics if `ptrs` and `out` have different lengths.
+///
+/// # Safety
+///
+/// `a` must be a power of two.
+pub unsafe fn align_offsets_simd(ptrs: &[usize], stride: usize, a: usize, out: &mut [usize]) {
+    align_offsets_isa(Isa::best(), ptrs, stride, a, out)
+}
+
+/// Like [`align_offsets`](crate::align_offsets), using the instruction set `isa`.
+///
+/// # Panics
+///
+/// Panics if `ptrs` and `out` have different lengths.
+///
+/// # Safety
+///
+/// `a` must be a power of two, and `isa` must be available on the running CPU.
+pub unsafe fn align_offsets_isa(
+    isa: Isa,
+    ptrs: &[usize],
+    stride: usize,
+    a: usize,
+    out: &mut [usize],
+) {
+    assert_eq!(
+        ptrs.len(),
+        out.len(),
+        "ptrs and out must have the same length"
+    );
+
+    let aligner = Aligner::new_unchecked(stride, a);
+    match isa {
+        #[cfg(target_arch = "x86_64")]
+        Isa::Sse2 => x86_64::align_offsets_sse2(&aligner, ptrs, out),
+        #[cfg(target_arch = "x86_64")]
+        Isa::Avx2 => x86_64::align_offsets_avx2(&aligner, ptrs, out),
+        _ => {
+            for (&p, o) in ptrs.iter().zip(out.iter_mut()) {
+                *o = aligner.offset(p);
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    //! Neither SSE2 nor AVX2 have 64-bit multiplication or 64-bit arithmetic shifts, so both are
+    //! emulated with their 32-bit counterparts.
+    //!
+    //! For each lane, this computes [`Aligner::offset`] as:
+    //!
+    //! ```text
+    //! offset = ((a2 - ((p & a_minus_one) >> gcdpow)) * inverse) & a2minus1
+    //! unalignable = (0 - (p & gcdminus1)) >>> 63  // arithmetic shift
+    //! offset | unalignable
+    //! ```
+    //!
+    //! where `unalignable` is all ones iff `p & gcdminus1 != 0`, since `gcdminus1 < 2^63`.
+
+    use super::Aligner;
+    use core::arch::x86_64::*;
+
+    /// Low 64 bits of the product of each pair of 64-bit lanes.
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn mullo_epi64_sse2(a: __m128i, b: __m128i) -> __m128i {
+        let lo = _mm_mul_epu32(a, b);
+        let cross = _mm_add_epi64(
+            _mm_mul_epu32(_mm_srli_epi64(a, 32), b),
+            _mm_mul_epu32(a, _mm_srli_epi64(b, 32)),
+        );
+        _mm_add_epi64(lo, _mm_slli_epi64(cross, 32))
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn align_offsets_sse2(aligner: &Aligner, ptrs: &[usize], out: &mut [usize]) {
+        let a_minus_one = _mm_set1_epi64x(aligner.a_minus_one as i64);
+        let gcdpow = _mm_cvtsi32_si128(aligner.gcdpow as i32);
+        let gcdminus1 = _mm_set1_epi64x(aligner.gcdminus1 as i64);
+        let a2 = _mm_set1_epi64x(aligner.a2 as i64);
+        let a2minus1 = _mm_set1_epi64x(aligner.a2minus1 as i64);
+        let inverse = _mm_set1_epi64x(aligner.inverse as i64);
+
+        let mut ptr_chunks = ptrs.chunks_exact(2);
+        let mut out_chunks = out.chunks_exact_mut(2);
+        for (ptr_chunk, out_chunk) in (&mut ptr_chunks).zip(&mut out_chunks) {
+            let p = _mm_loadu_si128(ptr_chunk.as_ptr() as *const __m128i);
+            let pmoda = _mm_and_si128(p, a_minus_one);
+            let minusp2 = _mm_sub_epi64(a2, _mm_srl_epi64(pmoda, gcdpow));
+            let offset = _mm_and_si128(mullo_epi64_sse2(minusp2, inverse), a2minus1);
+            let negated = _mm_sub_epi64(_mm_setzero_si128(), _mm_and_si128(p, gcdminus1));
+            // Broadcast the sign bit of the high half of each lane to the whole lane.
+            let unalignable = _mm_shuffle_epi32(_mm_srai_epi32(negated, 31), 0b11_11_01_01);
+            let result = _mm_or_si128(offset, unalignable);
+            _mm_storeu_si128(out_chunk.as_mut_ptr() as *mut __m128i, result);
+        }
+        for (&p, o) in ptr_chunks
+            .remainder()
+            .iter()
+            .zip(out_chunks.into_remainder())
+        {
+            *o = aligner.offset(p);
+        }
+    }
+
+    /// Low 64 bits of the product of each pair of 64-bit lanes.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn mullo_epi64_avx2(a: __m256i, b: __m256i) -> __m256i {
+        let lo = _mm256_mul_epu32(a, b);
+        let cross = _mm256_add_epi64(
+            _mm256_mul_epu32(_mm256_srli_epi64(a, 32), b),
+            _mm256_mul_epu32(a, _mm256_srli_epi64(b, 32)),
+        );
+        _mm256_add_epi64(lo, _mm256_slli_epi64(cross, 32))
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn align_offsets_avx2(aligner: &Aligner, ptrs: &[usize], out: &mut [usize]) {
+        let a_minus_one = _mm256_set1_epi64x(aligner.a_minus_one as i64);
+        let gcdpow = _mm_cvtsi32_si128(aligner.gcdpow as i32);
+        let gcdminus1 = _mm256_set1_epi64x(aligner.gcdminus1 as i64);
+        let a2 = _mm256_set1_epi64x(aligner.a2 as i64);
+        let a2minus1 = _mm256_set1_epi64x(aligner.a2minus1 as i64);
+        let inverse = _mm256_set1_epi64x(aligner.inverse as i64);
+
+        let mut ptr_chunks = ptrs.chunks_exact(4);
+        let mut out_chunks = out.chunks_exact_mut(4);
+        for (ptr_chunk, out_chunk) in (&mut ptr_chunks).zip(&mut out_chunks) {
+            let p = _mm256_loadu_si256(ptr_chunk.as_ptr() as *const __m256i);
+            let pmoda = _mm256_and_si256(p, a_minus_one);
+            let minusp2 = _mm256_sub_epi64(a2, _mm256_srl_epi64(pmoda, gcdpow));
+            let offset = _mm256_and_si256(mullo_epi64_avx2(minusp2, inverse), a2minus1);
+            let negated = _mm256_sub_epi64(_mm256_setzero_si256(), _mm256_and_si256(p, gcdminus1));
+            // Broadcast the sign bit of the high half of each lane to the whole lane.
+            let unalignable = _mm256_shuffle_epi32(_mm256_srai_epi32(negated, 31), 0b11_11_01_01);
+            let result = _mm256_or_si256(offset, unalignable);
+            _mm256_storeu_si256(out_chunk.as_mut_ptr() as *mut __m256i, result);
+        }
+        for (&p, o) in ptr_chunks
+            .remainder()
+            .iter()
+            .zip(out_chunks.into_remainder())
+        {
+            *o = aligner.offset(p);
+        }
+    }
+}
+
+#[test]
+fn align_offsets_simd_matches_v4() {
+    // An odd length, so that the remainders are exercised too.
+    let mut out = [0; 2047];
+    for isa in Isa::ALL.iter().copied().filter(|isa| isa.is_available()) {
+        for apow in 0..usize::BITS {
+            let align = 1usize << apow;
+            let ptrs: Vec<usize> = crate::test_ptrs(align, 1024).take(out.len()).collect();
+            for stride in crate::test_strides() {
+                unsafe { align_offsets_isa(isa, &ptrs, stride, align, &mut out) };
+                for (&ptr, &got) in ptrs.iter().zip(out.iter()) {
+                    let expected = unsafe { crate::align_offset_v4(ptr, stride, align) };
+                    assert_eq!(
+                        got,
+                        expected,
+                        "{}: aligning {:x} (with stride of {}) to {}",
+                        isa.name(),
+                        ptr,
+                        stride,
+                        align
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! `slice::align_to`, the main consumer of `align_offset`, on top of a selectable variant.
+//!
+//! Transmuting the middle of the slice from `T` to `U` cannot be made safe in general. The safe
+//! [`align_to`] and [`align_to_mut`] are limited to types implementing [`AnyBitPattern`], while
+//! the `_unchecked` forms have the same contract as their `core` counterparts. Everything else is
+//! checked.
+
+use crate::ptr::{AlignOffsetExt, AlignOffsetVariant};
+use core::mem;
+use core::slice;
+
+/// Types which are valid for any bit pattern, and have no padding bytes, so that any of their
+/// values can be reinterpreted as any other such type of the same size.
+///
+/// # Safety
+///
+/// Every initialized sequence of `size_of::<Self>()` bytes must be a valid `Self`, and every byte
+/// of every `Self` must be initialized.
+pub unsafe trait AnyBitPattern: Copy {}
+
+/// Implement [`AnyBitPattern`] for the given primitive types.
+macro_rules! any_bit_pattern {
+    ($($t:ty),*) => {
+        $(unsafe impl AnyBitPattern for $t {})*
+    };
+}
+
+// SAFETY: the integers have no padding, and every bit pattern is one of their values.
+any_bit_pattern!((), u8, u16, u32, u64, u128, usize);
+any_bit_pattern!(i8, i16, i32, i64, i128, isize);
+
+// SAFETY: arrays have no padding between their elements.
+unsafe impl<T: AnyBitPattern, const N: usize> AnyBitPattern for [T; N] {}
+
+/// Greatest common divisor of `a` and `b`, which must not both be zero.
+const fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// The lengths of the `[U]` middle and `[T]` suffix of an aligned `[T]` of length `len`.
+fn middle_and_suffix_lens<T, U>(len: usize) -> (usize, usize) {
+    // The middle must span a whole number of both `T`s and `U`s, so it is built of units of
+    // `lcm(size_of::<T>(), size_of::<U>())` bytes, i.e. `ts` elements of `T` or `us` of `U`.
+    // The loop in `gcd` is not reliably folded by the optimizer, so it is forced at compile time.
+    let gcd = const { gcd(mem::size_of::<T>(), mem::size_of::<U>()) };
+    let ts = mem::size_of::<U>() / gcd;
+    let us = mem::size_of::<T>() / gcd;
+    (len / ts * us, len % ts)
+}
+
+/// Split `slice` into a prefix, a middle of aligned `U`s and a suffix, like
+/// `<[T]>::align_to::<U>`, computing the length of the prefix with the variant `V`.
+///
+/// # Safety
+///
+/// Same as `<[T]>::align_to`: transmuting the middle elements to `U` must be valid.
+///
+/// # Panics
+///
+/// Panics if the alignment of `U` is larger than `V` supports.
+pub unsafe fn align_to_unchecked<V: AlignOffsetVariant, T, U>(slice: &[T]) -> (&[T], &[U], &[T]) {
+    if mem::size_of::<T>() == 0 || mem::size_of::<U>() == 0 {
+        return (slice, &[], &[]);
+    }
+    let offset = slice.as_ptr().align_offset_with::<V>(mem::align_of::<U>());
+    if offset > slice.len() {
+        return (slice, &[], &[]);
+    }
+    let (prefix, rest) = slice.split_at(offset);
+    let (middle_len, suffix_len) = middle_and_suffix_lens::<T, U>(rest.len());
+    // SAFETY: `rest` starts aligned for `U`, and the middle spans at most the bytes of `rest`
+    // before the suffix. The caller guarantees that these bytes are valid `U`s.
+    let middle = slice::from_raw_parts(rest.as_ptr().cast::<U>(), middle_len);
+    let suffix = &rest[rest.len() - suffix_len..];
+    (prefix, middle, suffix)
+}
+
+/// Split `slice` into a prefix, a middle of aligned `U`s and a suffix, like
+/// `<[T]>::align_to_mut::<U>`, computing the length of the prefix with the variant `V`.
+///
+/// # Safety
+///
+/// Same as `<[T]>::align_to_mut`: transmuting the middle elements to `U`, and back, must be valid.
+///
+/// # Panics
+///
+/// Panics if the alignment of `U` is larger than `V` supports.
+pub unsafe fn align_to_mut_unchecked<V: AlignOffsetVariant, T, U>(
+    slice: &mut [T],
+) -> (&mut [T], &mut [U], &mut [T]) {
+    if mem::size_of::<T>() == 0 || mem::size_of::<U>() == 0 {
+        return (slice, &mut [], &mut []);
+    }
+    let offset = slice.as_ptr().align_offset_with::<V>(mem::align_of::<U>());
+    if offset > slice.len() {
+        return (slice, &mut [], &mut []);
+    }
+    let (prefix, rest) = slice.split_at_mut(offset);
+    let (middle_len, suffix_len) = middle_and_suffix_lens::<T, U>(rest.len());
+    let suffix_start = rest.len() - suffix_len;
+    let (middle, suffix) = rest.split_at_mut(suffix_start);
+    // SAFETY: `middle` starts aligned for `U`, and `middle_len` `U`s span at most its bytes. The
+    // caller guarantees that these bytes are valid `U`s, and `T`s again.
+    let middle = slice::from_raw_parts_mut(middle.as_mut_ptr().cast::<U>(), middle_len);
+    (prefix, middle, suffix)
+}
+
+/// Split `slice` into a prefix, a middle of aligned `U`s and a suffix, like
+/// `<[T]>::align_to::<U>`, computing the length of the prefix with the variant `V`.
+///
+/// # Panics
+///
+/// Panics if the alignment of `U` is larger than `V` supports.
+#[inline]
+pub fn align_to<V: AlignOffsetVariant, T: AnyBitPattern, U: AnyBitPattern>(
+    slice: &[T],
+) -> (&[T], &[U], &[T]) {
+    // SAFETY: the bytes of the `T`s are all initialized, and any of them are a valid `U`.
+    unsafe { align_to_unchecked::<V, T, U>(slice) }
+}
+
+/// Split `slice` into a prefix, a middle of aligned `U`s and a suffix, like
+/// `<[T]>::align_to_mut::<U>`, computing the length of the prefix with the variant `V`.
+///
+/// # Panics
+///
+/// Panics if the alignment of `U` is larger than `V` supports.
+#[inline]
+pub fn align_to_mut<V: AlignOffsetVariant, T: AnyBitPattern, U: AnyBitPattern>(
+    slice: &mut [T],
+) -> (&mut [T], &mut [U], &mut [T]) {
+    // SAFETY: the bytes of the `T`s are all initialized, and any of them are a valid `U`, and the
+    // same holds for the `U`s written back.
+    unsafe { align_to_mut_unchecked::<V, T, U>(slice) }
+}
+
+#[test]
+fn align_to_matches_core() {
+    /// A buffer of bytes, aligned for any of the types below.
+    #[repr(C, align(64))]
+    struct Buffer([u8; 512]);
+
+    /// The address and length of each part of a split.
+    type Parts = [(usize, usize); 3];
+
+    fn parts<T, U>((prefix, middle, suffix): (&[T], &[U], &[T])) -> Parts {
+        [
+            (prefix.as_ptr() as usize, prefix.len()),
+            (middle.as_ptr() as usize, middle.len()),
+            (suffix.as_ptr() as usize, suffix.len()),
+        ]
+    }
+
+    fn parts_mut<T, U>((prefix, middle, suffix): (&mut [T], &mut [U], &mut [T])) -> Parts {
+        parts::<T, U>((prefix, middle, suffix))
+    }
+
+    fn check<V: AlignOffsetVariant, T: AnyBitPattern, U: AnyBitPattern>(buffer: &mut Buffer) {
+        let size = mem::size_of::<T>().max(1);
+        let mut start = 0;
+        while start < 64 {
+            let max_len = (buffer.0.len() - start) / size;
+            for len in (0..=max_len.min(40)).chain([max_len]) {
+                // SAFETY: `start` is a multiple of the alignment of `T`, and the slice stays in
+                // the buffer. All the types below are plain integers, valid for any bytes.
+                let slice = unsafe {
+                    slice::from_raw_parts_mut(buffer.0.as_mut_ptr().add(start).cast::<T>(), len)
+                };
+                let expected = parts::<T, U>(unsafe { slice.align_to::<U>() });
+                let got = parts::<T, U>(align_to::<V, T, U>(slice));
+                assert_eq!(
+                    got,
+                    expected,
+                    "{}: [{}; {}] at {} to {}",
+                    V::VARIANT.name,
+                    core::any::type_name::<T>(),
+                    len,
+                    start,
+                    core::any::type_name::<U>()
+                );
+                let expected = parts_mut::<T, U>(unsafe { slice.align_to_mut::<U>() });
+                let got = parts_mut::<T, U>(align_to_mut::<V, T, U>(slice));
+                assert_eq!(got, expected);
+            }
+            start += mem::align_of::<T>();
+        }
+    }
+
+    macro_rules! check_all {
+        ($buffer:expr; $($t:ty),*) => {
+            check_all!(@t $buffer; [$($t),*]; $($t),*);
+        };
+        (@t $buffer:expr; $us:tt; $($t:ty),*) => {
+            $(check_all!(@u $buffer; $t; $us);)*
+        };
+        (@u $buffer:expr; $t:ty; [$($u:ty),*]) => {
+            $(
+                crate::for_each_variant_marker!(V => check::<V, $t, $u>($buffer));
+            )*
+        };
+    }
+
+    let mut buffer = Buffer([0; 512]);
+    check_all!(
+        &mut buffer;
+        (), u8, u16, [u8; 3], u32, [u16; 3], u64, [u8; 24], [u32; 5], u128
+    );
+}
@@ -1,6 +1,16 @@
-use core::intrinsics;
+use crate::word::Word;
 
-pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
+/// Calculate the offset, in elements of size `stride`, that needs to be applied to the address `p`
+/// for it to become aligned to `a`, or `W::MAX` if no such offset exists.
+///
+/// # Safety
+///
+/// `a` must be a power of two.
+///
+/// Only alignments up to 16, or no larger than `2^(W::BITS / 2)`, are supported: for larger ones,
+/// the modulus in `mod_inv` is squared past `W::MAX` and wraps around to 0. With debug assertions
+/// enabled, this then panics with an arithmetic overflow, and otherwise it never returns.
+pub unsafe fn align_offset<W: Word>(p: W, stride: W, a: W) -> W {
     /// Calculate multiplicative modular inverse of `x` modulo `m`.
     ///
     /// This implementation is tailored for align_offset and has following preconditions:
@@ -8,22 +18,25 @@ pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
     /// * `m` is a power-of-two;
     /// * `x < m`; (if `x ≥ m`, pass in `x % m` instead)
     ///
-    /// Implementation of this function shall not panic. Ever.
+    /// Implementation of this function shall not panic for any `m` up to the supported
+    /// alignments above.
     #[inline]
-    fn mod_inv(x: usize, m: usize) -> usize {
+    fn mod_inv<W: Word>(x: W, m: W) -> W {
         /// Multiplicative modular inverse table modulo 2⁴ = 16.
         ///
         /// Note, that this table does not contain values where inverse does not exist (i.e., for
         /// `0⁻¹ mod 16`, `2⁻¹ mod 16`, etc.)
         const INV_TABLE_MOD_16: [u8; 8] = [1, 11, 13, 7, 9, 3, 5, 15];
         /// Modulo for which the `INV_TABLE_MOD_16` is intended.
-        const INV_TABLE_MOD: usize = 16;
-        /// INV_TABLE_MOD²
-        const INV_TABLE_MOD_SQUARED: usize = INV_TABLE_MOD * INV_TABLE_MOD;
+        const INV_TABLE_MOD: u8 = 16;
+        // INV_TABLE_MOD², which wraps around to 0 for `u8`.
+        let inv_table_mod_squared =
+            W::from_u8(INV_TABLE_MOD).wrapping_mul(W::from_u8(INV_TABLE_MOD));
 
-        let table_inverse = INV_TABLE_MOD_16[(x & (INV_TABLE_MOD - 1)) >> 1] as usize;
-        if m <= INV_TABLE_MOD {
-            table_inverse & (m - 1)
+        let table_inverse =
+            W::from_u8(INV_TABLE_MOD_16[(x.low_u8() & (INV_TABLE_MOD - 1)) as usize >> 1]);
+        if m <= W::from_u8(INV_TABLE_MOD) {
+            table_inverse & (m - W::ONE)
         } else {
             // We iterate "up" using the following formula:
             //
@@ -31,37 +44,37 @@ pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
             //
             // until 2²ⁿ ≥ m. Then we can reduce to our desired `m` by taking the result `mod m`.
             let mut inverse = table_inverse;
-            let mut going_mod = INV_TABLE_MOD_SQUARED;
+            let mut going_mod = inv_table_mod_squared;
             loop {
                 // y = y * (2 - xy) mod n
                 //
                 // Note, that we use wrapping operations here intentionally – the original formula
-                // uses e.g., subtraction `mod n`. It is entirely fine to do them `mod
-                // usize::max_value()` instead, because we take the result `mod n` at the end
-                // anyway.
-                inverse = inverse.wrapping_mul(2usize.wrapping_sub(x.wrapping_mul(inverse)))
-                    & (going_mod - 1);
+                // uses e.g., subtraction `mod n`. It is entirely fine to do them `mod 2^W::BITS`
+                // instead, as wrapping operations on `W` do, because we take the result `mod n` at
+                // the end anyway.
+                inverse = inverse.wrapping_mul(W::from_u8(2).wrapping_sub(x.wrapping_mul(inverse)))
+                    & (going_mod - W::ONE);
                 if going_mod >= m {
-                    return inverse & (m - 1);
+                    return inverse & (m - W::ONE);
                 }
                 going_mod = going_mod.wrapping_mul(going_mod);
             }
         }
     }
 
-    let a_minus_one = a.wrapping_sub(1);
+    let a_minus_one = a.wrapping_sub(W::ONE);
     let pmoda = p & a_minus_one;
 
-    if pmoda == 0 {
+    if pmoda == W::ZERO {
         // Already aligned. Yay!
-        return 0;
+        return W::ZERO;
     }
 
-    if stride <= 1 {
-        return if stride == 0 {
+    if stride <= W::ONE {
+        return if stride == W::ZERO {
             // If the pointer is not aligned, and the element is zero-sized, then no amount of
             // elements will ever align the pointer.
-            !0
+            !W::ZERO
         } else {
             a.wrapping_sub(pmoda)
         };
@@ -69,10 +82,10 @@ pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
 
     let smoda = stride & a_minus_one;
     // a is power-of-two so cannot be 0. stride = 0 is handled above.
-    let gcdpow = intrinsics::cttz_nonzero(stride).min(intrinsics::cttz_nonzero(a));
-    let gcd = 1usize << gcdpow;
+    let gcdpow = stride.cttz_nonzero().min(a.cttz_nonzero());
+    let gcd = W::ONE << gcdpow;
 
-    if p & (gcd - 1) == 0 {
+    if p & (gcd - W::ONE) == W::ZERO {
         // This branch solves for the following linear congruence equation:
         //
         // $$ p + so ≡ 0 mod a $$
@@ -91,9 +104,69 @@ pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
         // to take the result $o mod lcm(s, a)$. We can replace $lcm(s, a)$ with just a $a / g$.
         let j = a.wrapping_sub(pmoda) >> gcdpow;
         let k = smoda >> gcdpow;
-        return (j.wrapping_mul(mod_inv(k, a))) & ((a >> gcdpow).wrapping_sub(1));
+        return (j.wrapping_mul(mod_inv(k, a))) & ((a >> gcdpow).wrapping_sub(W::ONE));
     }
 
     // Cannot be aligned at all.
-    usize::max_value()
+    W::MAX
+}
+
+/// A `const` twin of [`align_offset`] on `usize`, for computing offsets of statically known layouts
+/// at compile time. See there for the derivation.
+///
+/// # Safety
+///
+/// Same as [`align_offset`].
+pub const unsafe fn align_offset_const(p: usize, stride: usize, a: usize) -> usize {
+    /// Like `mod_inv` in [`align_offset`].
+    const fn mod_inv(x: usize, m: usize) -> usize {
+        const INV_TABLE_MOD_16: [u8; 8] = [1, 11, 13, 7, 9, 3, 5, 15];
+        const INV_TABLE_MOD: usize = 16;
+        const INV_TABLE_MOD_SQUARED: usize = INV_TABLE_MOD * INV_TABLE_MOD;
+
+        let table_inverse = INV_TABLE_MOD_16[(x & (INV_TABLE_MOD - 1)) >> 1] as usize;
+        if m <= INV_TABLE_MOD {
+            table_inverse & (m - 1)
+        } else {
+            let mut inverse = table_inverse;
+            let mut going_mod = INV_TABLE_MOD_SQUARED;
+            loop {
+                inverse = inverse.wrapping_mul(2usize.wrapping_sub(x.wrapping_mul(inverse)))
+                    & (going_mod - 1);
+                if going_mod >= m {
+                    return inverse & (m - 1);
+                }
+                going_mod = going_mod.wrapping_mul(going_mod);
+            }
+        }
+    }
+
+    let a_minus_one = a.wrapping_sub(1);
+    let pmoda = p & a_minus_one;
+
+    if pmoda == 0 {
+        return 0;
+    }
+
+    if stride <= 1 {
+        return if stride == 0 {
+            !0
+        } else {
+            a.wrapping_sub(pmoda)
+        };
+    }
+
+    let smoda = stride & a_minus_one;
+    let stridepow = stride.trailing_zeros();
+    let apow = a.trailing_zeros();
+    let gcdpow = if stridepow < apow { stridepow } else { apow };
+    let gcd = 1usize << gcdpow;
+
+    if p & (gcd - 1) == 0 {
+        let j = a.wrapping_sub(pmoda) >> gcdpow;
+        let k = smoda >> gcdpow;
+        return (j.wrapping_mul(mod_inv(k, a))) & ((a >> gcdpow).wrapping_sub(1));
+    }
+
+    usize::MAX
 }
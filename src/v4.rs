@@ -1,6 +1,12 @@
-use core::intrinsics;
+use crate::word::Word;
 
-pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
+/// Calculate the offset, in elements of size `stride`, that needs to be applied to the address `p`
+/// for it to become aligned to `a`, or `W::MAX` if no such offset exists.
+///
+/// # Safety
+///
+/// `a` must be a power of two.
+pub unsafe fn align_offset<W: Word>(p: W, stride: W, a: W) -> W {
     /// Calculate multiplicative modular inverse of `x` modulo `m`, where
     /// `m = 2^mpow` and `mask = m - 1`.
     ///
@@ -14,20 +20,21 @@ pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
     ///
     /// Implementation of this function shall not panic. Ever.
     #[inline]
-    fn mod_pow_2_inv(x: usize, mpow: usize, mask: usize) -> usize {
+    fn mod_pow_2_inv<W: Word>(x: W, mpow: u32, mask: W) -> W {
         /// Multiplicative modular inverse table modulo 2⁴ = 16.
         ///
         /// Note, that this table does not contain values where inverse does not exist (i.e., for
         /// `0⁻¹ mod 16`, `2⁻¹ mod 16`, etc.)
         const INV_TABLE_MOD_16: [u8; 8] = [1, 11, 13, 7, 9, 3, 5, 15];
         /// Modulo for which the `INV_TABLE_MOD_16` is intended.
-        const INV_TABLE_MOD: usize = 1 << INV_TABLE_MOD_POW;
+        const INV_TABLE_MOD: u8 = 1 << INV_TABLE_MOD_POW;
         /// `s` such that `INV_TABLE_MOD == 2^s`.
-        const INV_TABLE_MOD_POW: usize = 4;
+        const INV_TABLE_MOD_POW: u32 = 4;
         /// `s` such that `INV_TABLE_MOD == 2^(s/2)`.
-        const INV_TABLE_MOD_POW_TIMES_2: usize = INV_TABLE_MOD_POW << 1;
+        const INV_TABLE_MOD_POW_TIMES_2: u32 = INV_TABLE_MOD_POW << 1;
 
-        let table_inverse = INV_TABLE_MOD_16[(x & (INV_TABLE_MOD - 1)) >> 1] as usize;
+        let table_inverse =
+            W::from_u8(INV_TABLE_MOD_16[(x.low_u8() & (INV_TABLE_MOD - 1)) as usize >> 1]);
 
         if mpow <= INV_TABLE_MOD_POW {
             table_inverse & mask
@@ -47,10 +54,10 @@ pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
                 // y = y * (2 - xy)
                 //
                 // Note, that we use wrapping operations here intentionally – the original formula
-                // uses e.g., subtraction `mod n`. It is entirely fine to do them `mod
-                // usize::max_value()` instead, because we take the result `mod n` at the end
-                // anyway.
-                inverse = inverse.wrapping_mul(2usize.wrapping_sub(x.wrapping_mul(inverse)));
+                // uses e.g., subtraction `mod n`. It is entirely fine to do them `mod 2^W::BITS`
+                // instead, as wrapping operations on `W` do, because we take the result `mod n` at
+                // the end anyway.
+                inverse = inverse.wrapping_mul(W::from_u8(2).wrapping_sub(x.wrapping_mul(inverse)));
                 if going_modpow >= mpow {
                     return inverse;
                 }
@@ -59,19 +66,19 @@ pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
         }
     }
 
-    let a_minus_one = a.wrapping_sub(1);
+    let a_minus_one = a.wrapping_sub(W::ONE);
     let pmoda = p & a_minus_one;
 
-    if pmoda == 0 {
+    if pmoda == W::ZERO {
         // Already aligned. Yay!
-        return 0;
+        return W::ZERO;
     }
 
-    if stride <= 1 {
-        return if stride == 0 {
+    if stride <= W::ONE {
+        return if stride == W::ZERO {
             // If the pointer is not aligned, and the element is zero-sized, then no amount of
             // elements will ever align the pointer.
-            !0
+            !W::ZERO
         } else {
             a.wrapping_sub(pmoda)
         };
@@ -79,11 +86,11 @@ pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
 
     let smoda = stride & a_minus_one;
     // a is power-of-two so cannot be 0. stride = 0 is handled above.
-    let apow = intrinsics::cttz_nonzero(a);
-    let gcdpow = intrinsics::cttz_nonzero(stride).min(apow);
-    let gcd = 1usize << gcdpow;
+    let apow = a.cttz_nonzero();
+    let gcdpow = stride.cttz_nonzero().min(apow);
+    let gcd = W::ONE << gcdpow;
 
-    if p & (gcd.wrapping_sub(1)) == 0 {
+    if p & (gcd.wrapping_sub(W::ONE)) == W::ZERO {
         // This branch solves for the following linear congruence equation:
         //
         // $$ p + so ≡ 0 mod a $$
@@ -106,15 +113,79 @@ pub unsafe fn align_offset(p: usize, stride: usize, a: usize) -> usize {
         // Furthermore, the result produced by this solution is not “minimal”, so it is necessary
         // to take the result $o mod lcm(s, a)$. We can replace $lcm(s, a)$ with just a $a'$.
         let a2 = a >> gcdpow;
-        let a2minus1 = a2.wrapping_sub(1);
+        let a2minus1 = a2.wrapping_sub(W::ONE);
         let s2 = smoda >> gcdpow;
         let minusp2 = a2.wrapping_sub(pmoda >> gcdpow);
         // mod_pow_2_inv returns a result which may be out of $a'$-s range, but it's fine to
-        // multiply modulu usize::max_value() here, and then take modulu $a'$ afterwards.
+        // multiply modulo `2^W::BITS` here, and then take modulo $a'$ afterwards.
         return (minusp2.wrapping_mul(mod_pow_2_inv(s2, apow.wrapping_sub(gcdpow), a2minus1)))
             & a2minus1;
     }
 
     // Cannot be aligned at all.
-    usize::max_value()
+    W::MAX
+}
+
+/// A `const` twin of [`align_offset`] on `usize`, for computing offsets of statically known layouts
+/// at compile time. See there for the derivation.
+///
+/// # Safety
+///
+/// Same as [`align_offset`].
+pub const unsafe fn align_offset_const(p: usize, stride: usize, a: usize) -> usize {
+    /// Like `mod_pow_2_inv` in [`align_offset`].
+    const fn mod_pow_2_inv(x: usize, mpow: u32, mask: usize) -> usize {
+        const INV_TABLE_MOD_16: [u8; 8] = [1, 11, 13, 7, 9, 3, 5, 15];
+        const INV_TABLE_MOD: usize = 1 << INV_TABLE_MOD_POW;
+        const INV_TABLE_MOD_POW: u32 = 4;
+        const INV_TABLE_MOD_POW_TIMES_2: u32 = INV_TABLE_MOD_POW << 1;
+
+        let table_inverse = INV_TABLE_MOD_16[(x & (INV_TABLE_MOD - 1)) >> 1] as usize;
+
+        if mpow <= INV_TABLE_MOD_POW {
+            table_inverse & mask
+        } else {
+            let mut inverse = table_inverse;
+            let mut going_modpow = INV_TABLE_MOD_POW_TIMES_2;
+            loop {
+                inverse = inverse.wrapping_mul(2usize.wrapping_sub(x.wrapping_mul(inverse)));
+                if going_modpow >= mpow {
+                    return inverse;
+                }
+                going_modpow <<= 1;
+            }
+        }
+    }
+
+    let a_minus_one = a.wrapping_sub(1);
+    let pmoda = p & a_minus_one;
+
+    if pmoda == 0 {
+        return 0;
+    }
+
+    if stride <= 1 {
+        return if stride == 0 {
+            !0
+        } else {
+            a.wrapping_sub(pmoda)
+        };
+    }
+
+    let smoda = stride & a_minus_one;
+    let apow = a.trailing_zeros();
+    let stridepow = stride.trailing_zeros();
+    let gcdpow = if stridepow < apow { stridepow } else { apow };
+    let gcd = 1usize << gcdpow;
+
+    if p & (gcd.wrapping_sub(1)) == 0 {
+        let a2 = a >> gcdpow;
+        let a2minus1 = a2.wrapping_sub(1);
+        let s2 = smoda >> gcdpow;
+        let minusp2 = a2.wrapping_sub(pmoda >> gcdpow);
+        return (minusp2.wrapping_mul(mod_pow_2_inv(s2, apow.wrapping_sub(gcdpow), a2minus1)))
+            & a2minus1;
+    }
+
+    usize::MAX
 }
@@ -0,0 +1,105 @@
+use crate::word::Word;
+
+/// Calculate the offset, in elements of size `stride`, that needs to be applied to the address `p`
+/// for it to become aligned to `a`, or `W::MAX` if no such offset exists.
+///
+/// # Safety
+///
+/// `a` must be a power of two.
+pub unsafe fn align_offset<W: Word>(p: W, stride: W, a: W) -> W {
+    /// Calculate multiplicative modular inverse of `x` modulo `2^W::BITS`, and hence modulo any
+    /// smaller power of two.
+    ///
+    /// This implementation is tailored for align_offset and has following preconditions:
+    ///
+    /// * `x` is odd.
+    ///
+    /// It also leaves reducing the result modulu the requested modulus to the caller.
+    ///
+    /// Implementation of this function shall not panic. Ever.
+    #[inline]
+    fn mod_word_inv<W: Word>(x: W) -> W {
+        /// `s` such that the seed below is valid mod `2^s`.
+        const SEED_MOD_POW: u32 = 5;
+
+        // Instead of a table lookup, we use the identity $x (3x ⊕ 2) ≡ 1 (mod 2⁵)$, which holds for
+        // every odd $x$.
+        let mut inverse = W::from_u8(3).wrapping_mul(x) ^ W::from_u8(2);
+        // We iterate "up" using the following formula:
+        //
+        // $$ xy ≡ 1 (mod 2ⁿ) → xy (2 - xy) ≡ 1 (mod 2²ⁿ) $$
+        //
+        // until 2ⁿ ≥ 2^W::BITS. The number of iterations only depends on `W`, so after
+        // monomorphization this loop is fully unrolled, without any branches.
+        let mut going_modpow = SEED_MOD_POW;
+        while going_modpow < W::BITS {
+            // y = y * (2 - xy)
+            //
+            // Note, that we use wrapping operations here intentionally – the original formula
+            // uses e.g., subtraction `mod n`. It is entirely fine to do them `mod 2^W::BITS`
+            // instead, as wrapping operations on `W` do, because we take the result `mod n` at
+            // the end anyway.
+            inverse = inverse.wrapping_mul(W::from_u8(2).wrapping_sub(x.wrapping_mul(inverse)));
+            going_modpow <<= 1;
+        }
+        inverse
+    }
+
+    let a_minus_one = a.wrapping_sub(W::ONE);
+    let pmoda = p & a_minus_one;
+
+    if pmoda == W::ZERO {
+        // Already aligned. Yay!
+        return W::ZERO;
+    }
+
+    if stride <= W::ONE {
+        return if stride == W::ZERO {
+            // If the pointer is not aligned, and the element is zero-sized, then no amount of
+            // elements will ever align the pointer.
+            !W::ZERO
+        } else {
+            a.wrapping_sub(pmoda)
+        };
+    }
+
+    let smoda = stride & a_minus_one;
+    // a is power-of-two so cannot be 0. stride = 0 is handled above.
+    let apow = a.cttz_nonzero();
+    let gcdpow = stride.cttz_nonzero().min(apow);
+    let gcd = W::ONE << gcdpow;
+
+    if p & (gcd.wrapping_sub(W::ONE)) == W::ZERO {
+        // This branch solves for the following linear congruence equation:
+        //
+        // $$ p + so ≡ 0 mod a $$
+        //
+        // $p$ here is the pointer value, $s$ – stride of `T`, $o$ offset in `T`s, and $a$ – the
+        // requested alignment.
+        //
+        // With $g = gcd(a, s)$$, and the above asserting that $p$ is also divisible by $g$, we can
+        // denote $a' = a/g$, $s' = s/g$, $p' = p/g$, then this becomes equivalent to:
+        //
+        // $$ p' + s'o ≡ 0 mod a' $$
+        // $$ o = (a' - (p' mod a')) * ((s')⁻¹ mod a')
+        //
+        // The first term is “the relative alignment of $p$ to $a$” (divided by the $g$), the second
+        // term is “how does incrementing $p$ by $s$ bytes change the relative alignment of $p$” (again
+        // divided by $g$).
+        // Division by $g$ is necessary to make the inverse well formed if $a$ and $s$ are not
+        // co-prime.
+        //
+        // Furthermore, the result produced by this solution is not “minimal”, so it is necessary
+        // to take the result $o mod lcm(s, a)$. We can replace $lcm(s, a)$ with just a $a'$.
+        let a2 = a >> gcdpow;
+        let a2minus1 = a2.wrapping_sub(W::ONE);
+        let s2 = smoda >> gcdpow;
+        let minusp2 = a2.wrapping_sub(pmoda >> gcdpow);
+        // mod_word_inv returns a result which may be out of $a'$-s range, but it's fine to
+        // multiply modulo `2^W::BITS` here, and then take modulo $a'$ afterwards.
+        return (minusp2.wrapping_mul(mod_word_inv(s2))) & a2minus1;
+    }
+
+    // Cannot be aligned at all.
+    W::MAX
+}
@@ -0,0 +1,81 @@
+use crate::word::Word;
+
+/// Calculate the offset, in elements of size `stride`, that needs to be applied to the address `p`
+/// for it to become aligned to `a`, or `W::MAX` if no such offset exists.
+///
+/// Unlike the other variants, this one has no branches at all, computing every case with masks and
+/// conditional selects.
+///
+/// # Safety
+///
+/// `a` must be a power of two.
+pub unsafe fn align_offset<W: Word>(p: W, stride: W, a: W) -> W {
+    /// Calculate multiplicative modular inverse of `x` modulo `2^W::BITS`, and hence modulo any
+    /// smaller power of two.
+    ///
+    /// This implementation is tailored for align_offset and has following preconditions:
+    ///
+    /// * `x` is odd.
+    ///
+    /// It also leaves reducing the result modulu the requested modulus to the caller.
+    ///
+    /// Implementation of this function shall not panic. Ever.
+    ///
+    /// This is the same as in `align_offset_v5`: like every variant, this one is kept
+    /// self-contained, so that it can be benchmarked and read on its own, and changing one variant
+    /// never changes the codegen of another.
+    #[inline]
+    fn mod_word_inv<W: Word>(x: W) -> W {
+        /// `s` such that the seed below is valid mod `2^s`.
+        const SEED_MOD_POW: u32 = 5;
+
+        // Instead of a table lookup, we use the identity $x (3x ⊕ 2) ≡ 1 (mod 2⁵)$, which holds for
+        // every odd $x$.
+        let mut inverse = W::from_u8(3).wrapping_mul(x) ^ W::from_u8(2);
+        // We iterate "up" using the following formula:
+        //
+        // $$ xy ≡ 1 (mod 2ⁿ) → xy (2 - xy) ≡ 1 (mod 2²ⁿ) $$
+        //
+        // until 2ⁿ ≥ 2^W::BITS. The number of iterations only depends on `W`, so after
+        // monomorphization this loop is fully unrolled, without any branches.
+        let mut going_modpow = SEED_MOD_POW;
+        while going_modpow < W::BITS {
+            // y = y * (2 - xy)
+            //
+            // Note, that we use wrapping operations here intentionally – the original formula
+            // uses e.g., subtraction `mod n`. It is entirely fine to do them `mod 2^W::BITS`
+            // instead, as wrapping operations on `W` do, because we take the result `mod n` at
+            // the end anyway.
+            inverse = inverse.wrapping_mul(W::from_u8(2).wrapping_sub(x.wrapping_mul(inverse)));
+            going_modpow <<= 1;
+        }
+        inverse
+    }
+
+    // All the special cases of the other variants fall out of the general solution below:
+    //
+    // * If $p$ is already aligned, $a' - (p' mod a') = a'$, and the result is $0 mod a'$;
+    // * If $s = 1$, then $g = 1$ and $s'⁻¹ = 1$, and the result is just $a - (p mod a)$;
+    // * If $s = 0$, `trailing_zeros` returns `W::BITS`, so $g = a$, and the result is $0 mod 1$
+    //   when $p$ is aligned, and unalignable otherwise, as $p$ is not divisible by $g$.
+    //
+    // See `align_offset_v4` for the derivation of the general solution.
+    let a_minus_one = a.wrapping_sub(W::ONE);
+    let pmoda = p & a_minus_one;
+    let smoda = stride & a_minus_one;
+    // a is power-of-two so cannot be 0.
+    let apow = a.cttz_nonzero();
+    let gcdpow = stride.trailing_zeros().min(apow);
+    let gcdminus1 = (W::ONE << gcdpow).wrapping_sub(W::ONE);
+
+    let a2minus1 = a_minus_one >> gcdpow;
+    let s2 = smoda >> gcdpow;
+    let minusp2 = (a >> gcdpow).wrapping_sub(pmoda >> gcdpow);
+    // mod_word_inv returns a result which may be out of $a'$-s range, but it's fine to
+    // multiply modulo `2^W::BITS` here, and then take modulo $a'$ afterwards.
+    let offset = (minusp2.wrapping_mul(mod_word_inv(s2))) & a2minus1;
+
+    // All ones if $p$ is not divisible by $g$, and so cannot be aligned at all, zero otherwise.
+    let unalignable = W::ZERO.wrapping_sub(W::from_u8((p & gcdminus1 != W::ZERO) as u8));
+    offset | unalignable
+}
@@ -0,0 +1,142 @@
+//! The unsigned integer types the variants can operate on.
+//!
+//! Besides `usize`, the variants can run on every other unsigned integer type, emulating targets
+//! with other pointer widths.
+//!
+//! With the `nightly` feature enabled, [`Word::cttz_nonzero`] and [`Word::unchecked_rem`] forward
+//! to the real `core::intrinsics`, so the benchmarks measure the codegen of the original `libcore`
+//! implementation. Without it, they are expressed through stable APIs carrying the same "this
+//! cannot be zero" hints, so that every variant builds on a stable toolchain.
+
+use core::fmt;
+#[cfg(not(feature = "nightly"))]
+use core::num::NonZero;
+use core::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr, Sub};
+
+/// An unsigned integer type, playing the role of `usize` in the variants.
+pub trait Word:
+    Copy
+    + Eq
+    + Ord
+    + fmt::Debug
+    + fmt::Display
+    + fmt::LowerHex
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + Sub<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+{
+    /// The size of this type in bits.
+    const BITS: u32;
+    /// `0`.
+    const ZERO: Self;
+    /// `1`.
+    const ONE: Self;
+    /// The largest value of this type.
+    const MAX: Self;
+
+    /// Convert a byte to this type.
+    fn from_u8(x: u8) -> Self;
+
+    /// The lowest byte of `self`.
+    fn low_u8(self) -> u8;
+
+    /// Wrapping (modular) addition.
+    fn wrapping_add(self, rhs: Self) -> Self;
+
+    /// Wrapping (modular) subtraction.
+    fn wrapping_sub(self, rhs: Self) -> Self;
+
+    /// Wrapping (modular) multiplication.
+    fn wrapping_mul(self, rhs: Self) -> Self;
+
+    /// Count trailing zeros of `self`, which is `Self::BITS` if `self` is zero.
+    fn trailing_zeros(self) -> u32;
+
+    /// Count trailing zeros of `self`, which must not be zero.
+    ///
+    /// # Safety
+    ///
+    /// `self` must be non-zero.
+    unsafe fn cttz_nonzero(self) -> u32;
+
+    /// Calculate `self % rhs`, where `rhs` must not be zero.
+    ///
+    /// # Safety
+    ///
+    /// `rhs` must be non-zero.
+    unsafe fn unchecked_rem(self, rhs: Self) -> Self;
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {$(
+        impl Word for $t {
+            const BITS: u32 = <$t>::BITS;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const MAX: Self = <$t>::MAX;
+
+            #[inline(always)]
+            fn from_u8(x: u8) -> Self {
+                x as $t
+            }
+
+            #[inline(always)]
+            fn low_u8(self) -> u8 {
+                self as u8
+            }
+
+            #[inline(always)]
+            fn wrapping_add(self, rhs: Self) -> Self {
+                <$t>::wrapping_add(self, rhs)
+            }
+
+            #[inline(always)]
+            fn wrapping_sub(self, rhs: Self) -> Self {
+                <$t>::wrapping_sub(self, rhs)
+            }
+
+            #[inline(always)]
+            fn wrapping_mul(self, rhs: Self) -> Self {
+                <$t>::wrapping_mul(self, rhs)
+            }
+
+            #[inline(always)]
+            fn trailing_zeros(self) -> u32 {
+                <$t>::trailing_zeros(self)
+            }
+
+            #[cfg(feature = "nightly")]
+            #[inline(always)]
+            unsafe fn cttz_nonzero(self) -> u32 {
+                core::intrinsics::cttz_nonzero(self)
+            }
+
+            #[cfg(not(feature = "nightly"))]
+            #[inline(always)]
+            unsafe fn cttz_nonzero(self) -> u32 {
+                NonZero::<$t>::new_unchecked(self).trailing_zeros()
+            }
+
+            #[cfg(feature = "nightly")]
+            #[inline(always)]
+            unsafe fn unchecked_rem(self, rhs: Self) -> Self {
+                core::intrinsics::unchecked_rem(self, rhs)
+            }
+
+            #[cfg(not(feature = "nightly"))]
+            #[inline(always)]
+            unsafe fn unchecked_rem(self, rhs: Self) -> Self {
+                if rhs == 0 {
+                    core::hint::unreachable_unchecked()
+                }
+                self % rhs
+            }
+        }
+    )*};
+}
+
+impl_word!(u8, u16, u32, u64, u128, usize);
@@ -0,0 +1,133 @@
+//! Property tests of the linear congruence every variant solves:
+//!
+//! $$ p + so ≡ 0 mod a $$
+//!
+//! with $o$ minimal, and the largest word returned when there is no solution. Each variant is only
+//! run with the alignments its registry entry declares as supported; larger ones are checked to be
+//! limited to v0–v2, and rejected by the checked wrapper.
+//!
+//! The same properties are checked for every word width the variants can run on, emulating
+//! targets with other pointer widths.
+
+use bench_align_offset::registry::AlignLimit;
+use bench_align_offset::{align_offset, AlignError, VARIANTS};
+use proptest::prelude::*;
+
+macro_rules! congruence_tests {
+    ($mod:ident, $w:ty, $($func:ident).+) => {
+        mod $mod {
+            use super::*;
+
+            /// Pointers, covering the whole address space but favouring small and nearly-aligned
+            /// values.
+            fn pointer() -> impl Strategy<Value = $w> {
+                prop_oneof![
+                    any::<$w>(),
+                    0..4096 as $w,
+                    (0..<$w>::BITS, any::<$w>()).prop_map(|(pow, p)| p << pow),
+                ]
+            }
+
+            /// Strides, covering zero, small odd and even sizes, powers of two and arbitrary
+            /// values.
+            fn stride() -> impl Strategy<Value = $w> {
+                prop_oneof![
+                    Just(0 as $w),
+                    1..256 as $w,
+                    (0..<$w>::BITS).prop_map(|pow| 1 << pow),
+                    any::<$w>(),
+                ]
+            }
+
+            /// Every power-of-two alignment up to `1 << (BITS - 1)`.
+            fn alignment() -> impl Strategy<Value = $w> {
+                (0..<$w>::BITS).prop_map(|pow| 1 << pow)
+            }
+
+            proptest! {
+                #![proptest_config(ProptestConfig::with_cases(4096))]
+
+                #[test]
+                fn solves_congruence(p in pointer(), stride in stride(), a in alignment()) {
+                    let a_minus_one = a - 1;
+                    // gcd(s, a), where gcd(0, a) = a.
+                    let gcd: $w = 1 << stride.trailing_zeros().min(a.trailing_zeros());
+                    for variant in VARIANTS {
+                        if a > variant.max_align() {
+                            prop_assert_ne!(variant.align_limit, AlignLimit::Unlimited,
+                                "{} skipped {}, which it supports", variant.name, a);
+                            continue;
+                        }
+                        let o = unsafe { (variant.$($func).+)(p, stride, a) };
+                        if p & (gcd - 1) != 0 {
+                            prop_assert_eq!(o, <$w>::MAX,
+                                "{} found an offset for an unalignable pointer", variant.name);
+                            continue;
+                        }
+                        prop_assert_ne!(o, <$w>::MAX, "{} found no offset", variant.name);
+                        // All solutions are congruent modulo a / gcd, so the one below it is the
+                        // minimal one.
+                        prop_assert!(o < a / gcd,
+                            "{} returned {}, not below {}", variant.name, o, a / gcd);
+                        prop_assert_eq!(p.wrapping_add(stride.wrapping_mul(o)) & a_minus_one, 0,
+                            "{} returned {}, which does not align", variant.name, o);
+                    }
+                }
+
+                #[test]
+                fn minimal_for_small_offsets(p in pointer(), stride in stride(), a in alignment()) {
+                    for variant in VARIANTS {
+                        if a > variant.max_align() {
+                            prop_assert_ne!(variant.align_limit, AlignLimit::Unlimited,
+                                "{} skipped {}, which it supports", variant.name, a);
+                            continue;
+                        }
+                        let o = unsafe { (variant.$($func).+)(p, stride, a) };
+                        for smaller in 0..o.min(1024) {
+                            prop_assert_ne!(
+                                p.wrapping_add(stride.wrapping_mul(smaller)) & (a - 1), 0,
+                                "{} returned {}, but {} already aligns", variant.name, o, smaller);
+                        }
+                    }
+                }
+            }
+        }
+    };
+}
+
+congruence_tests!(word_usize, usize, func);
+congruence_tests!(word_u16, u16, word_funcs.u16);
+congruence_tests!(word_u32, u32, word_funcs.u32);
+congruence_tests!(word_u64, u64, word_funcs.u64);
+congruence_tests!(word_u128, u128, word_funcs.u128);
+
+/// The alignments skipped above are exactly those past the limits of v0–v2, and the checked
+/// wrapper rejects them rather than running the variant.
+#[test]
+fn rejects_unsupported_alignments() {
+    let limited: Vec<_> = VARIANTS
+        .iter()
+        .filter(|variant| variant.align_limit != AlignLimit::Unlimited)
+        .map(|variant| variant.name)
+        .collect();
+    assert_eq!(
+        limited,
+        ["align_offset_v0", "align_offset_v1", "align_offset_v2"]
+    );
+    for variant in VARIANTS {
+        for pow in 0..usize::BITS {
+            let a = 1usize << pow;
+            let got = align_offset(variant, 8, 24, a);
+            if a > variant.max_align() {
+                assert_eq!(
+                    got,
+                    Err(AlignError::UnsupportedAlignment(a)),
+                    "{}",
+                    variant.name
+                );
+            } else {
+                assert!(got.is_ok(), "{} rejected {}", variant.name, a);
+            }
+        }
+    }
+}
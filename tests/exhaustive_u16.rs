@@ -0,0 +1,177 @@
+//! Exhaustive verification of the variants running on `u16` words, emulating a target with 16-bit
+//! pointers, against the naive oracle.
+//!
+//! Every `(pointer, stride, alignment)` triple is checked, spread across all cores. This takes a
+//! while, so it is only built with the `exhaustive-tests` feature:
+//!
+//! ```sh
+//! cargo test --release --features exhaustive-tests --test exhaustive_u16
+//! ```
+
+use bench_align_offset::{Variant, VARIANTS};
+use std::process;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+
+/// Mismatches of a single variant against the oracle.
+#[derive(Clone, Copy, Default)]
+struct Mismatches {
+    /// Number of triples for which the variant disagrees with the oracle.
+    count: u64,
+    /// The first disagreement found, as `(ptr, stride, align, expected, got)`.
+    first: Option<(u16, u16, u16, u16, u16)>,
+}
+
+impl Mismatches {
+    fn merge(&mut self, other: Mismatches) {
+        self.count += other.count;
+        // Keep the disagreement with the smallest stride, regardless of which thread found it.
+        match (self.first, other.first) {
+            (Some(first), Some(other_first)) if other_first.1 < first.1 => {
+                self.first = Some(other_first)
+            }
+            (None, other_first) => self.first = other_first,
+            _ => (),
+        }
+    }
+}
+
+/// The naive oracle, finding the *first* aligned element of stride::<T> by trying them in order,
+/// for all pointers at once: walking `el` upwards, the first `el` to hit the residue `-ptr mod
+/// align` is the answer for all pointers with that residue.
+fn naive_align_offsets(stride: u16, align: u16, offsets: &mut Vec<u16>) {
+    offsets.clear();
+    offsets.resize(align as usize, u16::MAX);
+    for el in 0..align {
+        let residue = 0u16.wrapping_sub(el.wrapping_mul(stride)) & (align - 1);
+        if offsets[residue as usize] == u16::MAX {
+            offsets[residue as usize] = el;
+        }
+    }
+}
+
+/// The naive oracle of `align_offset_weird_strides` itself, for a single pointer.
+fn naive_align_offset(ptr: u16, stride: u16, align: u16) -> u16 {
+    for el in 0..align {
+        if ptr.wrapping_add(el.wrapping_mul(stride)) & (align - 1) == 0 {
+            return el;
+        }
+    }
+    u16::MAX
+}
+
+/// Check [`naive_align_offsets`] against [`naive_align_offset`], for every stride at a couple of
+/// alignments. Both only depend on the pointer modulo the alignment, so one of each residue is
+/// enough.
+fn check_oracle() {
+    let mut offsets = Vec::new();
+    for stride in 0..=u16::MAX {
+        for align in [8, 64] {
+            naive_align_offsets(stride, align, &mut offsets);
+            for ptr in 0..align {
+                assert_eq!(
+                    offsets[ptr as usize],
+                    naive_align_offset(ptr, stride, align),
+                    "oracle: aligning {:x} (with stride of {}) to {}",
+                    ptr,
+                    stride,
+                    align
+                );
+            }
+        }
+    }
+    // And every pointer, for a few strides at the largest alignment.
+    for stride in [0, 1, 3, 24, 1 << 8, u16::MAX] {
+        let align = 1 << (u16::BITS - 1);
+        naive_align_offsets(stride, align, &mut offsets);
+        for ptr in 0..=u16::MAX {
+            assert_eq!(
+                offsets[(ptr & (align - 1)) as usize],
+                naive_align_offset(ptr, stride, align),
+                "oracle: aligning {:x} (with stride of {}) to {}",
+                ptr,
+                stride,
+                align
+            );
+        }
+    }
+    println!("oracle: agrees with the naive loop");
+}
+
+/// Check all pointers and alignments for a single stride.
+fn check_stride(stride: u16, offsets: &mut Vec<u16>, mismatches: &mut [Mismatches]) {
+    for apow in 0..u16::BITS {
+        let align = 1u16 << apow;
+        naive_align_offsets(stride, align, offsets);
+        for (variant, mismatches) in VARIANTS.iter().zip(mismatches.iter_mut()) {
+            if align > variant.max_align() {
+                continue;
+            }
+            let func = variant.word_funcs.u16;
+            for ptr in 0..=u16::MAX {
+                let expected = offsets[(ptr & (align - 1)) as usize];
+                let got = unsafe { func(ptr, stride, align) };
+                if got != expected {
+                    mismatches.count += 1;
+                    mismatches
+                        .first
+                        .get_or_insert((ptr, stride, align, expected, got));
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    check_oracle();
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let next_stride = AtomicU32::new(0);
+
+    let mut mismatches = vec![Mismatches::default(); VARIANTS.len()];
+    thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut offsets = Vec::new();
+                    let mut mismatches = vec![Mismatches::default(); VARIANTS.len()];
+                    loop {
+                        let stride = next_stride.fetch_add(1, Ordering::Relaxed);
+                        if stride > u16::MAX as u32 {
+                            return mismatches;
+                        }
+                        check_stride(stride as u16, &mut offsets, &mut mismatches);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            for (total, found) in mismatches.iter_mut().zip(handle.join().unwrap()) {
+                total.merge(found);
+            }
+        }
+    });
+
+    let mut x = false;
+    for (variant, mismatches) in VARIANTS.iter().zip(&mismatches) {
+        report(variant, mismatches);
+        x |= mismatches.count != 0;
+    }
+    if x {
+        process::exit(1);
+    }
+}
+
+fn report(variant: &Variant, mismatches: &Mismatches) {
+    let max_align: u16 = variant.max_align();
+    println!(
+        "{}: {} mismatches (alignments up to {})",
+        variant.name, mismatches.count, max_align
+    );
+    if let Some((ptr, stride, align, expected, got)) = mismatches.first {
+        println!(
+            "    first: aligning {:x} (with stride of {}) to {}, expected {}, got {}",
+            ptr, stride, align, expected, got
+        );
+    }
+}
@@ -0,0 +1,43 @@
+//! Exhaustive verification of the variants running on `u8` words, emulating a target with 8-bit
+//! pointers, against the naive oracle.
+
+use bench_align_offset::VARIANTS;
+
+/// Naive but definitely correct way to find the *first* aligned element of stride::<T>, in `u8`
+/// arithmetic.
+fn naive_align_offset(ptr: u8, stride: u8, align: u8) -> u8 {
+    for el in 0..align {
+        if ptr.wrapping_add(el.wrapping_mul(stride)) & (align - 1) == 0 {
+            return el;
+        }
+    }
+    u8::MAX
+}
+
+#[test]
+fn align_offset_u8_exhaustive() {
+    let mut x = false;
+    for variant in VARIANTS {
+        let max_align: u8 = variant.max_align();
+        for apow in 0..u8::BITS {
+            let align = 1u8 << apow;
+            if align > max_align {
+                break;
+            }
+            for ptr in 0..=u8::MAX {
+                for stride in 0..=u8::MAX {
+                    let expected = naive_align_offset(ptr, stride, align);
+                    let got = unsafe { (variant.word_funcs.u8)(ptr, stride, align) };
+                    if got != expected {
+                        eprintln!(
+                            "{}: aligning {:x} (with stride of {}) to {}, expected {}, got {}",
+                            variant.name, ptr, stride, align, expected, got
+                        );
+                        x = true;
+                    }
+                }
+            }
+        }
+    }
+    assert!(!x);
+}